@@ -116,8 +116,8 @@ pub fn run_cube<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
             let title = format!("[Cube: sigma={}, theta={}]", cube.sigma, cube.theta);
             let canvas = Canvas::default()
                 .block(Block::default().title(title).borders(Borders::ALL))
-                .x_bounds([0.0, (cols-1.0) as f64])
-                .y_bounds([0.0, (rows-1.0) as f64])
+                .x_bounds([0.0, cols-1.0])
+                .y_bounds([0.0, rows-1.0])
                 .marker(symbols::Marker::Braille)
                 .paint(|ctx| {
                     for line in cube.rotation(origin_x, origin_y) {ctx.draw(&line)}
@@ -1,235 +1,1693 @@
-use std::{io, format, time::{Duration, Instant}};
+use std::{io, fs, format, mem, thread, collections::HashMap, path::Path, time::{Duration, Instant}};
 use tui::{
     backend::Backend,
-    widgets::{Block, Borders, Paragraph, canvas::Canvas},
-    layout::{Layout, Constraint},
+    widgets::{Block, Borders, Paragraph, Clear, List, ListItem, ListState, Sparkline, canvas::{Canvas, Context}},
+    layout::{Layout, Constraint, Rect},
     style::{Color, Style},
-    text::Span,
+    text::{Span, Spans},
     symbols,
     Frame,
     Terminal
 };
-use crossterm::{
-    event::{self, Event, KeyCode},
-    terminal::size,
-};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+
+mod formats;
+mod cli;
+mod patterns;
+mod history;
+mod view;
+mod hashlife;
+mod bitgrid;
+mod stats;
+mod cycles;
+mod census;
+mod soups;
+mod rules;
+mod theme;
+mod activity;
+mod tools;
+mod images;
+mod breakpoints;
+mod prompt;
+mod snapshots;
+mod wireworld;
+mod turmites;
+mod elementary;
+mod immigration;
+mod mode;
+pub use cli::run_cli;
+pub use wireworld::{run_wireworld, run_wireworld_cli};
+pub use turmites::{run_turmites, run_turmites_cli};
+pub use elementary::{run_elementary, run_elementary_cli};
+pub use immigration::{run_immigration, run_immigration_cli};
+use history::{History, Change, Revision};
+use patterns::{Library, CATEGORIES, PATTERNS};
+use view::Viewport;
+use hashlife::HashLife;
+use bitgrid::BitGrid;
+use stats::Stats;
+use cycles::{CycleDetector, Detection};
+use census::{Census, Kind};
+use soups::Soup;
+use rules::Rule;
+use theme::{Theme, THEMES};
+use activity::{Activity, Shading, HEAT_SPAN};
+use tools::{Tool, Mirror};
+use breakpoints::Breakpoint;
+use prompt::{Prompt, Ask};
+use snapshots::Snapshots;
 
-const HELP: &str = "[s]: 'swap cell state', [p]: 'pause/resume game', [c]: 'clear grid', [arrows]: 'move cursor'";
+const HELP: &str = "[s]: 'swap cell state', [p]: 'pause/resume game', [c]: 'clear grid', [arrows]: 'move cursor', [l]: 'pattern library', [v]: 'select', [P]: 'paste', [u/U]: 'undo/redo', [b]: 'step back', [shift+arrows]: 'pan', [+/-]: 'faster/slower', [i/o]: 'zoom in/out', [0]: 'fit to population', [e]: 'switch engine', [j]: 'jump (hashlife)', [[/]]: 'jump size', [t]: 'threads', [I]: 'stats', [g]: 'population graph', [E]: 'export statistics', [N]: 'run N generations', [Z]: 'run until generation', [B]: 'pause on low population', [a]: 'pause on cycle', [X]: 'clear breakpoints', [C]: 'census', [R]: 'random soup', [V]: 'soup from seed', [</>]: 'soup density', [S]: 'soup symmetry', [h]: 'cell shading', [T]: 'theme', [m]: 'renderer', [w]: 'drawing tool', [M]: 'mirror', [G]: 'export image', [A]: 'export animation', [k]: 'take snapshot', [L]: 'snapshots', [W]: 'save game', [O]: 'load saved game', [D]: 'board size', [?]: 'all keys'";
+const SELECTION_HELP: &str = "[arrows]: 'resize selection', [y]: 'copy', [x]: 'cut', [d]: 'clear', [R]: 'random fill', [G/A]: 'export', [K]: 'pause on births', [r]: 'rotate', [f/F]: 'flip', [esc]: 'cancel'";
+const MAX_JUMP: u8 = 48;
+// generations per second, one step past the last is turbo \\
+const SPEEDS: [f64; 9] = [0.5, 1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 30.0, 60.0];
+const DEFAULT_SPEED: usize = 3;
+const TURBO_BUDGET: Duration = Duration::from_millis(40);
+const GRAPH_HEIGHT: u16 = 7;
+const LIBRARY_WIDTH: u16 = 26;
+const SNAPSHOTS_WIDTH: u16 = 34;
+const CENSUS_WIDTH: u16 = 40;
+const STATS_FILE: &str = "life-stats.csv";
+const IMAGE_FILE: &str = "life.png";
+const ANIMATION_FILE: &str = "life.gif";
+const SAVE_FILE: &str = "life-save.rle";
+const ANIMATION_GENERATIONS: u64 = 100;
+const EXPORT_CELL: usize = 8;
+const MAX_EXPORT_CELL: usize = 64;
+const EXPORT_DELAY: u16 = 10;
+const PROMPT_HELP: &str = "[enter]: 'confirm', [backspace]: 'erase', [esc]: 'cancel'";
+const MORE_HELP: &str = "[?]: 'all keys'";
+const SNAPSHOTS_HELP: [&str; 4] = ["[up/down]: 'select'", "[enter]: 'restore'", "[delete]: 'remove'", "[k]: 'take snapshot'"];
+const LIBRARY_HELP: [&str; 4] = ["[tab]: 'next pattern'", "[r]: 'rotate'", "[f/F]: 'flip'", "[enter]: 'stamp'"];
 
 
 struct Cursor {
-    x: usize,
-    y: usize,
+    x: i64,
+    y: i64,
+}
+
+impl Cursor {
+    // moves by one character, scrolling to keep the cursor in view \\
+    fn shift(&mut self, view: &mut Viewport, dx: i64, dy: i64) {
+        let step = view.cell_step();
+        self.x += dx * step;
+        self.y += dy * step;
+        view.follow(self.x, self.y);
+    }
+
+    // pans the view and drags the cursor along if it goes off screen \\
+    fn pan(&mut self, view: &mut Viewport, dx: i64, dy: i64) {
+        view.pan(dx, dy);
+        let (w, h) = view.span();
+        self.x = self.x.clamp(view.x, view.x + w - 1);
+        self.y = self.y.clamp(view.y, view.y + h - 1);
+    }
+}
+
+// time between two generations, none in turbo \\
+fn step_interval(speed: usize) -> Duration {
+    match SPEEDS.get(speed) {
+        Some(speed) => Duration::from_secs_f64(1.0 / speed),
+        None => Duration::ZERO,
+    }
+}
+
+fn describe_speed(speed: usize, measured: f64) -> String {
+    match SPEEDS.get(speed) {
+        Some(speed) => format!("{} gen/s", speed),
+        None if measured > 0.0 => format!("turbo, {:.0} gen/s", measured),
+        None => String::from("turbo"),
+    }
 }
 
 
 struct Game {
-    running_time: u16,
     paused: bool,
-    cols: f64,
-    rows: f64,
-    show_history: bool,
+    view: Viewport,
+    shading: Shading,
+    theme: usize,
+    activity: Activity,
     cursor: Cursor,
+    show_library: bool,
+    show_snapshots: bool,
+    snapshots: Snapshots,
+    library: Library,
+    selection: Option<Cursor>,
+    clipboard: Option<Population>,
+    jump_exponent: u8,
+    speed: usize,
+    measured_speed: f64,
+    show_stats: bool,
+    step_time: Duration,
+    show_graph: bool,
+    show_help: bool,
+    status: Option<String>,
+    breakpoints: Vec<Breakpoint>,
+    prompt: Option<Prompt>,
+    detection: Option<Detection>,
+    census: Option<Census>,
+    soup: Soup,
+    tool: Tool,
+    mark: Option<(i64, i64)>,
+    mirror: Mirror,
 }
 
 
 impl Game {
-    fn new(c: f64, r: f64) -> Game {
+    fn new(c: i64, r: i64) -> Game {
         Game {
-            running_time: 0,
             paused: true, 
-            cols: c,
-            rows: r, 
-            show_history: false, 
-            cursor: Cursor {
-                x: (c as usize)/2,
-                y: (r as usize)/2,
-            }, 
+            view: Viewport::new(c, r),
+            shading: Shading::Plain,
+            theme: 0,
+            activity: Activity::new(),
+            cursor: Cursor {x: 0, y: 0}, 
+            show_library: false,
+            show_snapshots: false,
+            snapshots: Snapshots::new(),
+            library: Library::new(),
+            selection: None,
+            clipboard: None,
+            jump_exponent: 10,
+            speed: DEFAULT_SPEED,
+            measured_speed: 0.0,
+            show_stats: false,
+            step_time: Duration::ZERO,
+            show_graph: false,
+            show_help: false,
+            status: None,
+            breakpoints: Vec::new(),
+            prompt: None,
+            detection: None,
+            census: None,
+            soup: Soup::new(),
+            tool: Tool::Pencil,
+            mark: None,
+            mirror: Mirror::Off,
+        }
+    }
+
+    fn move_cursor(&mut self, dx: i64, dy: i64) {
+        self.cursor.shift(&mut self.view, dx, dy);
+    }
+
+    fn pan(&mut self, dx: i64, dy: i64) {
+        self.cursor.pan(&mut self.view, dx, dy);
+    }
+
+    // bottom left corner of a pattern centered on the cursor \\
+    fn centered_origin(&self, pattern: &Population) -> (i64, i64) {
+        let (cols, rows) = pattern.dimensions();
+        (self.cursor.x - cols / 2, self.cursor.y - rows / 2)
+    }
+
+    // (min_x, min_y, max_x, max_y) spanned by the selection anchor and the cursor \\
+    fn selection_rect(&self) -> Option<(i64, i64, i64, i64)> {
+        self.selection.as_ref().map(|anchor| (
+            anchor.x.min(self.cursor.x),
+            anchor.y.min(self.cursor.y),
+            anchor.x.max(self.cursor.x),
+            anchor.y.max(self.cursor.y),
+        ))
+    }
+
+    // looks for a cycle in the new state, returns the detection the first time it is made \\
+    fn watch_cycles(&mut self, detector: &mut CycleDetector, population: &Population) -> Option<Detection> {
+        let detection = detector.observe(population);
+        self.detection = detector.detection();
+        detection
+    }
+
+    // pauses on the breakpoints hit by a step, dropping reached generation targets \\
+    fn check_breakpoints(&mut self, before: &Population, population: &Population, detection: Option<Detection>) {
+        let hits: Vec<(Breakpoint, String)> = self.breakpoints.iter()
+            .filter_map(|breakpoint| breakpoint.check(before, population, detection).map(|reason| (*breakpoint, reason)))
+            .collect();
+        if hits.is_empty() {
+            return;
+        }
+        self.paused = true;
+        self.status = Some(hits.iter().map(|(_, reason)| reason.as_str()).collect::<Vec<_>>().join(", "));
+        self.breakpoints.retain(|breakpoint| !(breakpoint.once() && hits.iter().any(|(hit, _)| hit == breakpoint)));
+    }
+
+    // whether a breakpoint needs the generation right before each step \\
+    fn compares_steps(&self) -> bool {
+        self.breakpoints.iter().any(Breakpoint::compares_steps)
+    }
+
+    // a generation target replaces the previous one, other breakpoints are toggled \\
+    fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if breakpoint.once() {
+            self.breakpoints.retain(|old| !old.once());
+            self.breakpoints.push(breakpoint);
+            self.paused = false;
+            self.status = Some(breakpoint.describe());
+        } else if let Some(idx) = self.breakpoints.iter().position(|old| *old == breakpoint) {
+            self.breakpoints.remove(idx);
+            self.status = Some(format!("removed: {}", breakpoint.describe()));
+        } else {
+            self.breakpoints.push(breakpoint);
+            self.status = Some(breakpoint.describe());
+        }
+    }
+
+    // edit key of the current tool: toggles a cell, drops a mark then draws a shape, or fills \\
+    fn use_tool(&mut self, population: &mut Population) {
+        let cursor = (self.cursor.x, self.cursor.y);
+        match self.tool {
+            Tool::Pencil => for (x, y) in self.mirror.images(cursor) {
+                population.switch(x, y);
+            }
+            Tool::Fill => match tools::flood_fill(cursor, population.frame(), population.size.is_some(), |x, y| population.get(x, y)) {
+                Some(region) => for (x, y) in self.mirror.apply(&region) {
+                    population.set(x, y, true);
+                }
+                None => self.status = Some(String::from("only enclosed dead regions can be filled")),
+            }
+            tool => match self.mark.take() {
+                None => self.mark = Some(cursor),
+                Some(mark) => for (x, y) in self.mirror.apply(&tool.shape(mark, cursor)) {
+                    population.set(x, y, true);
+                }
+            }
+        }
+    }
+
+    // shape that the next press of the edit key would draw, as far as it shows \\
+    fn tool_preview(&self, visible: (i64, i64, i64, i64)) -> Vec<(i64, i64)> {
+        match self.mark {
+            Some(mark) => tools::preview(self.tool, self.mirror, mark, (self.cursor.x, self.cursor.y), visible),
+            None => Vec::new(),
+        }
+    }
+
+    fn turbo(&self) -> bool {
+        self.speed == SPEEDS.len()
+    }
+
+    fn step_interval(&self) -> Duration {
+        step_interval(self.speed)
+    }
+
+    fn describe_speed(&self) -> String {
+        describe_speed(self.speed, self.measured_speed)
+    }
+
+    fn theme(&self) -> &'static Theme {
+        &THEMES[self.theme]
+    }
+
+    // keeps the per-cell activity, when shown, in line with the population \\
+    fn track_activity(&mut self, population: &Population, stepped: bool) {
+        if self.shading.needs_activity() {
+            self.activity.update(population, stepped);
+        }
+    }
+
+    // frames the board, or the pattern on the plane, with the cursor in the middle \\
+    fn fit_board(&mut self, population: &Population) {
+        if let Some(bounding_box) = population.size.map(|_| population.frame()).or_else(|| population.bounding_box()) {
+            self.view.fit(bounding_box);
+            self.cursor = Cursor {x: self.view.x + self.view.span().0 / 2, y: self.view.y + self.view.span().1 / 2};
+        }
+    }
+
+    // fills the selection, or the visible part of the plane, with the soup \\
+    // fills the selection, else the whole board, else the visible cells of the plane \\
+    fn fill_soup(&mut self, population: &mut Population) {
+        let (w, h) = self.view.span();
+        let (x0, y0, x1, y1) = match (self.selection_rect(), population.size) {
+            (Some(rect), _) => rect,
+            (None, Some(_)) => population.frame(),
+            (None, None) => (self.view.x, self.view.y, self.view.x + w - 1, self.view.y + h - 1),
+        };
+        self.soup.fill(population, x0, y0, x1, y1);
+        self.status = Some(self.soup.describe());
+    }
+
+    // keeps the census, when shown, in line with the population \\
+    fn refresh_census(&mut self, population: &Population) {
+        if self.census.is_some() {
+            self.census = Some(Census::take(population));
+        }
+    }
+
+    // replaces the selected region, and the rectangle a rotation turns it into, by a transformed copy \\
+    fn transform_selection(&mut self, population: &mut Population, transform: fn(&Population) -> Population) {
+        if let Some((x0, y0, x1, y1)) = self.selection_rect() {
+            let region = transform(&population.extract(x0, y0, x1, y1));
+            let (cols, rows) = region.dimensions();
+            population.clear_region(x0, y0, x1, y1);
+            population.clear_region(x0, y0, x0 + cols - 1, y0 + rows - 1);
+            population.stamp(&region, x0, y0);
+            self.selection = Some(Cursor {x: x0, y: y0});
+            self.cursor.x = x0 + cols - 1;
+            self.cursor.y = y0 + rows - 1;
         }
     }
 }
 
 
+// A population either lives on an unbounded plane or, for patterns and
+// clipboard contents, on a board of `size` cells outside which nothing lives.
+#[derive(Clone)]
 struct Population {
-    current_generation: Vec<Vec<i8>>,
-    dying_generation: Vec<(f64, f64)>,
-    ghost_generation: Vec<(f64, f64)>,
-    cols: usize,
-    rows: usize,
+    engine: Engine,
+    size: Option<(i64, i64)>,
+    threads: usize,
+    generation: u64,
+    rule: Rule,
+}
+
+
+#[derive(Clone)]
+enum Engine {
+    Packed(Box<BitGrid>),
+    HashLife(HashLife),
 }
 
 
 impl Population {
-    fn new(c: usize, r: usize) -> Population {
+    fn new() -> Population {
         Population {
-            current_generation: vec![vec![0; c]; r],
-            dying_generation: Vec::new(),
-            ghost_generation: Vec::new(),
-            cols: c,
-            rows: r,
+            engine: Engine::Packed(Box::new(BitGrid::new())),
+            size: None,
+            threads: max_threads(),
+            generation: 0,
+            rule: Rule::default(),
         }
     }
 
-    fn get_next_gen(&mut self) {
-        let mut next_gen: Vec<Vec<i8>> = vec![vec![0; self.cols]; self.rows];
+    fn with_size(cols: i64, rows: i64) -> Population {
+        Population {
+            size: Some((cols, rows)),
+            ..Population::new()
+        }
+    }
 
-        for y in 0..self.rows {
-            for x in 0..self.cols {
-                let cell_state = self.current_generation[y][x];
+    fn from_hashlife(hashlife: HashLife) -> Population {
+        Population {
+            engine: Engine::HashLife(hashlife),
+            ..Population::new()
+        }
+    }
 
-                // count neighbors \\
-                let mut live_neighbors = -cell_state;
-                for i in -1i16..=1 {
-                    for j in -1i16..=1 {
-                        let new_x = (x as i16) + i;
-                        let new_y = (y as i16) + j;
+    // the same cells on the unbounded plane, to run a loaded pattern \\
+    fn unbounded(mut self) -> Population {
+        self.size = None;
+        self
+    }
 
-                        if new_x > 0 && new_y > 0 && new_x < self.cols as i16 && new_y < self.rows as i16 {
-                            live_neighbors += self.current_generation[new_y as usize][new_x as usize];
-                        }
-                    }
-                }
+    // the cells moved onto a board of another size, or the unbounded plane, with the count left out \\
+    fn resized(&self, size: Option<(i64, i64)>) -> (Population, usize) {
+        let mut board = match size {
+            Some((cols, rows)) => Population::with_size(cols, rows),
+            None => Population::new(),
+        };
+        board.threads = self.threads;
+        board.generation = self.generation;
+        board.rule = self.rule;
+        let (dx, dy) = match (size, self.bounding_box()) {
+            (Some((cols, rows)), Some((x0, y0, x1, y1))) => (fit_offset(x0, x1, cols), fit_offset(y0, y1, rows)),
+            _ => (0, 0),
+        };
+        let mut clipped = 0;
+        for (x, y) in self.live_cells() {
+            if board.in_bounds(x + dx, y + dy) {
+                board.set(x + dx, y + dy, true);
+            } else {
+                clipped += 1;
+            }
+        }
+        (board, clipped)
+    }
 
-                // underpopulation \\
-                if cell_state == 1 && live_neighbors < 2 {
-                    next_gen[y][x] = 0;
-                }
-                // overpopulation \\
-                else if cell_state == 1 && live_neighbors > 3 {
-                    next_gen[y][x] = 0;
-                }
-                // reproduction \\
-                else if cell_state == 0 && live_neighbors == 3 {
-                    next_gen[y][x] = 1;
-                }
-                // stable population \\
-                else {
-                    next_gen[y][x] = cell_state;
-                }
+    fn in_bounds(&self, x: i64, y: i64) -> bool {
+        match self.size {
+            Some((cols, rows)) => x >= 0 && y >= 0 && x < cols && y < rows,
+            None => true,
+        }
+    }
+
+    fn engine_name(&self) -> &'static str {
+        match self.engine {
+            Engine::Packed(_) => "packed",
+            Engine::HashLife(_) => "hashlife",
+        }
+    }
+
+    // doubles the thread count of the packed engine, wrapping back to one \\
+    fn cycle_threads(&mut self) {
+        self.threads = if self.threads >= max_threads() {1} else {(self.threads * 2).min(max_threads())};
+    }
+
+    fn is_hashlife(&self) -> bool {
+        matches!(self.engine, Engine::HashLife(_))
+    }
+
+    // moves the live cells over to the other engine \\
+    fn switch_engine(&mut self) {
+        let cells = self.live_cells();
+        let mut population = match self.engine {
+            Engine::Packed(_) => Population::from_hashlife(HashLife::new()),
+            Engine::HashLife(_) => Population::new(),
+        };
+        for (x, y) in cells {
+            population.set(x, y, true);
+        }
+        self.engine = population.engine;
+    }
+
+    fn tile_count(&self) -> Option<usize> {
+        match &self.engine {
+            Engine::Packed(grid) => Some(grid.tile_count()),
+            Engine::HashLife(_) => None,
+        }
+    }
+
+    fn get_next_gen(&mut self) {
+        match &mut self.engine {
+            Engine::Packed(grid) => grid.step(self.size, self.threads, self.rule),
+            Engine::HashLife(hashlife) => {
+                hashlife.set_rule(self.rule);
+                hashlife.step(0);
             }
         }
-        self.ghost_generation = self.dying_generation.clone();
-        self.dying_generation = Vec::new();
-        for y in 0..self.rows {
-            for x in 0..self.cols {
-                if self.current_generation[y][x] == 1 {
-                    self.dying_generation.push((x as f64, y as f64));
+        self.generation += 1;
+    }
+
+    // advances 2^j generations at once \\
+    fn jump(&mut self, j: u8) {
+        match &mut self.engine {
+            Engine::Packed(_) => (0..1u64 << j).for_each(|_| self.get_next_gen()),
+            Engine::HashLife(hashlife) => {
+                hashlife.set_rule(self.rule);
+                hashlife.step(j);
+                self.generation += 1 << j;
+            }
+        }
+    }
+
+    // calls `f` with every 64x64 tile holding live cells, bit x of row y being cell (64 tx + x, 64 ty + y) \\
+    fn for_each_tile(&self, mut f: impl FnMut((i64, i64), &[u64; 64])) {
+        match &self.engine {
+            Engine::Packed(grid) => grid.tiles().for_each(|(&key, rows)| f(key, rows)),
+            Engine::HashLife(hashlife) => hashlife.for_each_tile(f),
+        }
+    }
+
+    fn count(&self) -> u64 {
+        match &self.engine {
+            Engine::Packed(grid) => grid.count(),
+            Engine::HashLife(hashlife) => hashlife.population(),
+        }
+    }
+
+    // (births, deaths) between an earlier state and this one \\
+    fn changes_since(&self, before: &Population) -> (u64, u64) {
+        let changes = match (&self.engine, &before.engine) {
+            (Engine::Packed(a), Engine::Packed(b)) => Some((a.new_cells(b), b.new_cells(a))),
+            (Engine::HashLife(a), Engine::HashLife(b)) => a.new_cells(b).zip(b.new_cells(a)),
+            _ => None,
+        };
+        changes.unwrap_or_else(|| {
+            let births = self.live_cells().into_iter().filter(|&(x, y)| !before.get(x, y)).count();
+            let deaths = before.live_cells().into_iter().filter(|&(x, y)| !self.get(x, y)).count();
+            (births as u64, deaths as u64)
+        })
+    }
+
+    fn switch(&mut self, x: i64, y: i64) {
+        let alive = self.get(x, y);
+        self.set(x, y, !alive);
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        match &self.engine {
+            Engine::Packed(grid) => grid.get(x, y),
+            Engine::HashLife(hashlife) => hashlife.get(x, y),
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if alive && !self.in_bounds(x, y) {
+            return;
+        }
+        match &mut self.engine {
+            Engine::Packed(grid) => grid.set(x, y, alive),
+            Engine::HashLife(hashlife) => hashlife.set(x, y, alive),
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        match self.bounding_box() {
+            Some((x0, y0, x1, y1)) => self.cells_in(x0, y0, x1, y1),
+            None => Vec::new(),
+        }
+    }
+
+    fn cells_in(&self, x0: i64, y0: i64, x1: i64, y1: i64) -> Vec<(i64, i64)> {
+        match &self.engine {
+            Engine::Packed(grid) => grid.cells_in(0, x0, y0, x1, y1),
+            Engine::HashLife(hashlife) => hashlife.cells_in(x0, y0, x1, y1),
+        }
+    }
+
+    // cells alive one or two generations ago, only kept by the packed engine \\
+    fn previous_cells_in(&self, age: usize, x0: i64, y0: i64, x1: i64, y1: i64) -> Vec<(i64, i64)> {
+        match &self.engine {
+            Engine::Packed(grid) => grid.cells_in(age, x0, y0, x1, y1),
+            Engine::HashLife(_) => Vec::new(),
+        }
+    }
+
+    fn same_cells(&self, other: &Population) -> bool {
+        match (&self.engine, &other.engine) {
+            (Engine::Packed(a), Engine::Packed(b)) => a.same_cells(b),
+            (Engine::HashLife(a), Engine::HashLife(b)) => a.same_cells(b),
+            _ => false,
+        }
+    }
+
+    // (min_x, min_y, max_x, max_y) of the board if bounded, of the live cells otherwise \\
+    fn frame(&self) -> (i64, i64, i64, i64) {
+        match self.size {
+            Some((cols, rows)) => (0, 0, cols - 1, rows - 1),
+            None => self.bounding_box().unwrap_or((0, 0, -1, -1)),
+        }
+    }
+
+    fn dimensions(&self) -> (i64, i64) {
+        let (x0, y0, x1, y1) = self.frame();
+        (x1 - x0 + 1, y1 - y0 + 1)
+    }
+
+    fn rotated(&self) -> Population {
+        let (_, y0, x1, _) = self.frame();
+        let (cols, rows) = self.dimensions();
+        let mut rotated = Population::with_size(rows, cols);
+        for (x, y) in self.live_cells() {
+            rotated.set(y - y0, x1 - x, true);
+        }
+        rotated
+    }
+
+    fn flipped_horizontal(&self) -> Population {
+        let (_, y0, x1, _) = self.frame();
+        let (cols, rows) = self.dimensions();
+        let mut flipped = Population::with_size(cols, rows);
+        for (x, y) in self.live_cells() {
+            flipped.set(x1 - x, y - y0, true);
+        }
+        flipped
+    }
+
+    fn flipped_vertical(&self) -> Population {
+        let (x0, _, _, y1) = self.frame();
+        let (cols, rows) = self.dimensions();
+        let mut flipped = Population::with_size(cols, rows);
+        for (x, y) in self.live_cells() {
+            flipped.set(x - x0, y1 - y, true);
+        }
+        flipped
+    }
+
+    fn extract(&self, x0: i64, y0: i64, x1: i64, y1: i64) -> Population {
+        let mut region = Population::with_size(x1 - x0 + 1, y1 - y0 + 1);
+        for (x, y) in self.cells_in(x0, y0, x1, y1) {
+            region.set(x - x0, y - y0, true);
+        }
+        region
+    }
+
+    fn clear_region(&mut self, x0: i64, y0: i64, x1: i64, y1: i64) {
+        match &mut self.engine {
+            Engine::Packed(grid) => grid.clear_region(x0, y0, x1, y1),
+            Engine::HashLife(_) => {
+                for (x, y) in self.cells_in(x0, y0, x1, y1) {
+                    self.set(x, y, false);
                 }
             }
         }
-        self.current_generation = next_gen;
     }
 
-    fn switch(&mut self, x: usize, y: usize) {
-        self.current_generation[y][x] = 1 - self.current_generation[y][x]
+    // copies the live cells of a pattern with its bottom left corner at (x, y) \\
+    fn stamp(&mut self, pattern: &Population, x: i64, y: i64) {
+        let (x0, y0, _, _) = pattern.frame();
+        for (px, py) in pattern.live_cells() {
+            self.set(x + px - x0, y + py - y0, true);
+        }
+    }
+
+    // (min_x, min_y, max_x, max_y) of the live cells \\
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        match &self.engine {
+            Engine::Packed(grid) => grid.bounding_box(),
+            Engine::HashLife(hashlife) => hashlife.bounding_box(),
+        }
     }
 
     fn kill_all(&mut self) {
-        self.current_generation = vec![vec![0; self.cols]; self.rows];
-        self.dying_generation = Vec::new();
-        self.ghost_generation = Vec::new();
+        self.engine = match self.engine {
+            Engine::Packed(_) => Engine::Packed(Box::new(BitGrid::new())),
+            Engine::HashLife(_) => Engine::HashLife(HashLife::new()),
+        };
+        self.generation = 0;
+    }
+}
+
+
+// cells flipped by a change of a packed population, with the generations
+// it went between \\
+struct PopulationDiff {
+    tiles: Vec<((i64, i64), [u64; 64])>,
+    generations: (u64, u64),
+}
+
+impl Revision for Population {
+    type Diff = PopulationDiff;
+
+    // hashlife populations and changes of board or rule are kept as copies \\
+    fn diff(&self, before: &Population) -> Option<PopulationDiff> {
+        match (&self.engine, &before.engine) {
+            (Engine::Packed(grid), Engine::Packed(old)) if self.size == before.size && self.rule == before.rule =>
+                Some(PopulationDiff {tiles: grid.changes(old), generations: (before.generation, self.generation)}),
+            _ => None,
+        }
+    }
+
+    fn revert(&mut self, diff: &PopulationDiff) {
+        if let Engine::Packed(grid) = &mut self.engine {
+            grid.flip(&diff.tiles);
+        }
+        self.generation = diff.generations.0;
+    }
+
+    fn apply(&mut self, diff: &PopulationDiff) {
+        if let Engine::Packed(grid) = &mut self.engine {
+            grid.flip(&diff.tiles);
+        }
+        self.generation = diff.generations.1;
+    }
+
+    fn footprint(&self) -> usize {
+        // hashlife nodes live in a shared arena, so a copy costs nothing \\
+        let cells = match &self.engine {
+            Engine::Packed(grid) => grid.footprint(),
+            Engine::HashLife(_) => 0,
+        };
+        mem::size_of::<Population>() + cells
+    }
+
+    fn diff_footprint(diff: &PopulationDiff) -> usize {
+        mem::size_of::<PopulationDiff>() + diff.tiles.capacity() * mem::size_of::<((i64, i64), [u64; 64])>()
+    }
+}
+
+
+// board dimensions written as `<cols>x<rows>` \\
+fn parse_board_size(text: &str) -> Option<(i64, i64)> {
+    let (cols, rows) = text.trim().split_once('x')?;
+    let (cols, rows) = (cols.parse().ok()?, rows.parse().ok()?);
+    if cols > 0 && rows > 0 {Some((cols, rows))} else {None}
+}
+
+// `<file> [cell size]` of an image export, both optional \\
+fn parse_export(text: &str) -> Option<(String, usize)> {
+    let mut words = text.split_whitespace();
+    let path = words.next().unwrap_or(IMAGE_FILE).to_string();
+    let cell = match words.next() {
+        Some(word) => word.parse().ok().filter(|cell| (1..=MAX_EXPORT_CELL).contains(cell))?,
+        None => EXPORT_CELL,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some((path, cell))
+}
+
+// how far to move cells spanning lo..=hi into 0..len: as little as possible, centered when too long \\
+fn fit_offset(lo: i64, hi: i64, len: i64) -> i64 {
+    if hi - lo + 1 > len {
+        (len - (hi - lo + 1)) / 2 - lo
+    } else if lo < 0 {
+        -lo
+    } else if hi >= len {
+        len - 1 - hi
+    } else {
+        0
     }
 }
 
+fn describe_board(size: Option<(i64, i64)>) -> String {
+    match size {
+        Some((cols, rows)) => format!("{}x{}", cols, rows),
+        None => String::from("unbounded"),
+    }
+}
+
+fn max_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// keys that may change the cells or the generation, the only ones worth a copy of the population \\
+fn edits_cells(code: KeyCode) -> bool {
+    matches!(code,
+        KeyCode::Char('u') | KeyCode::Char('U') | KeyCode::Char('b') | KeyCode::Char('n') | KeyCode::Char('j')
+        | KeyCode::Char('c') | KeyCode::Char('s') | KeyCode::Char('x') | KeyCode::Char('d') | KeyCode::Char('R')
+        | KeyCode::Char('r') | KeyCode::Char('f') | KeyCode::Char('F') | KeyCode::Char('P') | KeyCode::Char('O')
+        | KeyCode::Enter)
+}
+
 
 pub fn run_gol<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+    run_life(terminal, Population::new())
+}
+
+fn run_life<B: Backend>(terminal: &mut Terminal<B>, mut population: Population) -> io::Result<()> {
     // grid creation \\
-    let (c, r) = size().unwrap();
-    let (cols, rows) = ((c-2) as usize, (r-5) as usize);
-    let mut population = Population::new(cols, rows);
-    let mut game = Game::new(cols as f64, rows as f64);
+    let area = terminal.size()?;
+    let mut game = Game::new(area.width as i64 - 2, area.height as i64 - 5);
+    game.fit_board(&population);
+    let mut history = History::new();
+    let mut stats = Stats::new(&population);
+    let mut detector = CycleDetector::new();
+    game.watch_cycles(&mut detector, &population);
 
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_millis(50);
-    let mut frame_rate: u64 = 400;
+    let mut speed_sample = (Instant::now(), population.generation);
     
     loop {
-        terminal.draw(|f| ui(f, &game, &population))?;
+        let area = terminal.size()?;
+        let graph_height = if game.show_graph {GRAPH_HEIGHT} else {0};
+        game.view.resize(area.width as i64 - 2, area.height as i64 - 5 - graph_height as i64);
+        terminal.draw(|f| ui(f, &game, &population, &stats))?;
 
         // time update \\
-        let timeout = tick_rate
+        let timeout = game.step_interval()
             .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+            .unwrap_or_else(|| Duration::from_secs(0))
+            .min(tick_rate);
 
         // input handler \\
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
+                game.status = None;
+                if let Some(prompt) = &mut game.prompt {
+                    match key.code {
+                        KeyCode::Char(c) if prompt.accepts(c) => prompt.text.push(c),
+                        KeyCode::Backspace => {
+                            prompt.text.pop();
+                        }
+                        KeyCode::Enter if prompt.ask == Ask::BoardSize => match prompt.text.trim() {
+                            "" => {
+                                let before = population.clone();
+                                population = population.resized(None).0;
+                                history.record(Change::Edit, before, &population);
+                                game.status = Some(String::from("board: unbounded"));
+                                game.prompt = None;
+                            }
+                            text => match parse_board_size(text) {
+                                Some(size) => {
+                                    let (board, clipped) = population.resized(Some(size));
+                                    let before = mem::replace(&mut population, board);
+                                    history.record(Change::Edit, before, &population);
+                                    game.view.fit(population.frame());
+                                    game.cursor = Cursor {x: size.0 / 2, y: size.1 / 2};
+                                    game.status = Some(match clipped {
+                                        0 => format!("board: {}", describe_board(population.size)),
+                                        _ => format!("board: {}, {} cells did not fit", describe_board(population.size), clipped),
+                                    });
+                                    game.prompt = None;
+                                }
+                                None => game.status = Some(format!("'{}' is not a board size", text)),
+                            },
+                        },
+                        KeyCode::Enter if prompt.ask == Ask::SoupSeed => match prompt.text.parse() {
+                            Ok(seed) => {
+                                let before = population.clone();
+                                game.soup.seed = seed;
+                                game.fill_soup(&mut population);
+                                history.record(Change::Edit, before, &population);
+                                game.prompt = None;
+                            }
+                            Err(_) => game.status = Some(format!("'{}' is not a seed", prompt.text)),
+                        },
+                        KeyCode::Enter if prompt.ask == Ask::ImageFile => match parse_export(&prompt.text) {
+                            Some((path, cell)) => {
+                                game.status = Some(match images::save_image(Path::new(&path), &population, game.selection_rect(), cell, game.theme()) {
+                                    Ok(()) => format!("image saved to {}", path),
+                                    Err(err) => format!("export failed: {}", err),
+                                });
+                                game.prompt = None;
+                            }
+                            None => game.status = Some(format!("'{}' is not a file and a cell size up to {}", prompt.text, MAX_EXPORT_CELL)),
+                        },
+                        KeyCode::Enter if prompt.ask == Ask::SnapshotName => {
+                            let snapshot = game.snapshots.take(&prompt.text, &population);
+                            game.status = Some(format!("snapshot '{}' taken", snapshot.name));
+                            game.prompt = None;
+                        }
+                        KeyCode::Enter => match prompt.breakpoint(population.generation) {
+                            Ok(breakpoint) => {
+                                game.prompt = None;
+                                game.add_breakpoint(breakpoint);
+                            }
+                            Err(err) => game.status = Some(err),
+                        },
+                        KeyCode::Esc => game.prompt = None,
+                        _ => {}
+                    }
+                    continue;
+                }
+                let before = if edits_cells(key.code) {Some(population.clone())} else {None};
+                let mut reloaded = false;
+                let shift = key.modifiers.contains(KeyModifiers::SHIFT);
                 match key.code {
                     KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Char('u') => history.undo(&mut population),
+                    KeyCode::Char('U') => history.redo(&mut population),
+                    KeyCode::Char('b') if game.paused => history.step_back(&mut population),
                     KeyCode::Char('p') => game.paused = !game.paused,
-                    KeyCode::Char('h') => game.show_history = !game.show_history,
-                    KeyCode::Char('n') => if game.paused {population.get_next_gen()},
+                    KeyCode::Char('h') => {
+                        game.shading = game.shading.next();
+                        game.activity.reset();
+                        game.track_activity(&population, false);
+                        game.status = Some(format!("shading: {}", game.shading.name()));
+                    }
+                    KeyCode::Char('T') => {
+                        game.theme = (game.theme + 1) % THEMES.len();
+                        game.status = Some(format!("theme: {}", game.theme().name));
+                    }
+                    KeyCode::Char('n') if game.paused => {
+                        let start = Instant::now();
+                        population.get_next_gen();
+                        game.step_time = start.elapsed();
+                    }
+                    KeyCode::Char('e') if population.size.is_some() => game.status = Some(String::from("hashlife only runs on an unbounded board")),
+                    KeyCode::Char('e') => population.switch_engine(),
+                    KeyCode::Char('t') => population.cycle_threads(),
+                    KeyCode::Char('I') => game.show_stats = !game.show_stats,
+                    KeyCode::Char('g') => game.show_graph = !game.show_graph,
+                    KeyCode::Char('?') => game.show_help = !game.show_help,
+                    KeyCode::Char('a') => game.add_breakpoint(Breakpoint::Stable),
+                    KeyCode::Char('N') => game.prompt = Some(Prompt::new(Ask::RunFor)),
+                    KeyCode::Char('Z') => game.prompt = Some(Prompt::new(Ask::RunUntil)),
+                    KeyCode::Char('B') => game.prompt = Some(Prompt::new(Ask::PopulationBelow)),
+                    KeyCode::Char('D') if population.is_hashlife() => game.status = Some(String::from("switch to the packed engine to bound the board")),
+                    KeyCode::Char('D') => game.prompt = Some(Prompt::new(Ask::BoardSize)),
+                    KeyCode::Char('K') => match game.selection_rect() {
+                        Some((x0, y0, x1, y1)) => {
+                            game.add_breakpoint(Breakpoint::Birth(x0, y0, x1, y1));
+                            game.selection = None;
+                        }
+                        None => game.status = Some(String::from("select a region to watch first")),
+                    },
+                    KeyCode::Char('X') => {
+                        game.breakpoints.clear();
+                        game.status = Some(String::from("breakpoints cleared"));
+                    }
+                    KeyCode::Char('C') => game.census = match game.census {
+                        Some(_) => None,
+                        None => Some(Census::take(&population)),
+                    },
+                    KeyCode::Char('E') => game.status = Some(match fs::write(STATS_FILE, stats.to_csv()) {
+                        Ok(()) => format!("statistics saved to {}", STATS_FILE),
+                        Err(err) => format!("export failed: {}", err),
+                    }),
+                    KeyCode::Char('G') => game.prompt = Some(Prompt::new(Ask::ImageFile)),
+                    KeyCode::Char('A') => {
+                        let saved = images::save_animation(Path::new(ANIMATION_FILE), &population, game.selection_rect(), ANIMATION_GENERATIONS, EXPORT_CELL, EXPORT_DELAY, game.theme());
+                        game.status = Some(match saved {
+                            Ok(()) => format!("{} generations saved to {}", ANIMATION_GENERATIONS, ANIMATION_FILE),
+                            Err(err) => format!("export failed: {}", err),
+                        });
+                    }
+                    KeyCode::Char('j') if population.is_hashlife() => population.jump(game.jump_exponent),
+                    KeyCode::Char('[') if game.jump_exponent > 0 => game.jump_exponent -= 1,
+                    KeyCode::Char(']') if game.jump_exponent < MAX_JUMP => game.jump_exponent += 1,
                     KeyCode::Char('c') => {
                         population.kill_all();
                         game.paused = true;
-                        game.running_time = 0;
-                    }
-                    KeyCode::Char('s') => {
-                        population.switch(game.cursor.x, game.cursor.y);
-                    }
-                    KeyCode::Enter => population.switch(game.cursor.x, game.cursor.y),
-                    KeyCode::Left => if game.cursor.x > 0 {game.cursor.x -= 1},
-                    KeyCode::Right => if game.cursor.x < game.cols as usize {game.cursor.x += 1},
-                    KeyCode::Up => if game.cursor.y < game.rows as usize {game.cursor.y += 1},
-                    KeyCode::Down => if game.cursor.y > 0 {game.cursor.y -= 1},
-                    KeyCode::Char('+') => if frame_rate < 1000 {frame_rate += 50},
-                    KeyCode::Char('-') => if frame_rate > 50 {frame_rate -= 50},
+                        stats = Stats::new(&population);
+                    }
+                    KeyCode::Char('s') => game.use_tool(&mut population),
+                    KeyCode::Char('w') => {
+                        game.tool = game.tool.next();
+                        game.mark = None;
+                        game.status = Some(format!("tool: {}", game.tool.name()));
+                    }
+                    KeyCode::Char('M') => {
+                        game.mirror = game.mirror.next(game.cursor.x, game.cursor.y);
+                        game.status = Some(game.mirror.describe());
+                    }
+                    KeyCode::Char('v') => game.selection = Some(Cursor {x: game.cursor.x, y: game.cursor.y}),
+                    KeyCode::Esc => {
+                        game.selection = None;
+                        game.mark = None;
+                    }
+                    KeyCode::Char('y') | KeyCode::Char('x') if game.selection.is_some() => {
+                        let (x0, y0, x1, y1) = game.selection_rect().unwrap();
+                        game.clipboard = Some(population.extract(x0, y0, x1, y1));
+                        if key.code == KeyCode::Char('x') {
+                            population.clear_region(x0, y0, x1, y1);
+                        }
+                        game.selection = None;
+                    }
+                    KeyCode::Char('d') if game.selection.is_some() => {
+                        let (x0, y0, x1, y1) = game.selection_rect().unwrap();
+                        population.clear_region(x0, y0, x1, y1);
+                    }
+                    KeyCode::Char('R') => {
+                        game.soup.reseed();
+                        game.fill_soup(&mut population);
+                    }
+                    KeyCode::Char('V') => game.prompt = Some(Prompt::new(Ask::SoupSeed)),
+                    KeyCode::Char('<') | KeyCode::Char('>') => {
+                        let delta = if key.code == KeyCode::Char('<') {-0.05} else {0.05};
+                        game.soup.density = (game.soup.density + delta).clamp(0.05, 0.95);
+                        game.status = Some(game.soup.describe());
+                    }
+                    KeyCode::Char('S') => {
+                        game.soup.symmetry = game.soup.symmetry.next();
+                        game.status = Some(game.soup.describe());
+                    }
+                    KeyCode::Char('r') if game.selection.is_some() => game.transform_selection(&mut population, Population::rotated),
+                    KeyCode::Char('f') if game.selection.is_some() => game.transform_selection(&mut population, Population::flipped_horizontal),
+                    KeyCode::Char('F') if game.selection.is_some() => game.transform_selection(&mut population, Population::flipped_vertical),
+                    KeyCode::Char('P') => if let Some(clipboard) = &game.clipboard {
+                        let (x, y) = game.centered_origin(clipboard);
+                        population.stamp(clipboard, x, y);
+                    }
+                    KeyCode::Char('l') => {
+                        game.show_library = !game.show_library;
+                        game.show_snapshots = false;
+                    }
+                    KeyCode::Char('L') => {
+                        game.show_snapshots = !game.show_snapshots;
+                        game.show_library = false;
+                    }
+                    KeyCode::Char('k') => game.prompt = Some(Prompt::new(Ask::SnapshotName)),
+                    KeyCode::Up if game.show_snapshots && !shift => game.snapshots.select(-1),
+                    KeyCode::Down if game.show_snapshots && !shift => game.snapshots.select(1),
+                    KeyCode::Enter if game.show_snapshots => if let Some(snapshot) = game.snapshots.selected() {
+                        population = snapshot.population.clone();
+                        reloaded = true;
+                        game.status = Some(format!("restored '{}'", snapshot.name));
+                    }
+                    KeyCode::Delete if game.show_snapshots => if let Some(snapshot) = game.snapshots.remove() {
+                        game.status = Some(format!("removed '{}'", snapshot.name));
+                    }
+                    KeyCode::Char('W') => game.status = Some(match snapshots::save(Path::new(SAVE_FILE), &population, &game.snapshots) {
+                        Ok(()) => format!("game saved to {}", SAVE_FILE),
+                        Err(err) => format!("save failed: {}", err),
+                    }),
+                    KeyCode::Char('O') => match snapshots::load(Path::new(SAVE_FILE)) {
+                        Ok((board, snapshots)) => {
+                            population = board;
+                            reloaded = true;
+                            game.snapshots = snapshots;
+                            game.fit_board(&population);
+                            game.status = Some(format!("game loaded from {}", SAVE_FILE));
+                        }
+                        Err(err) => game.status = Some(format!("load failed: {}", err)),
+                    },
+                    KeyCode::Tab if game.show_library => game.library.next(),
+                    KeyCode::BackTab if game.show_library => game.library.previous(),
+                    KeyCode::Char('r') if game.show_library => game.library.rotate(),
+                    KeyCode::Char('f') if game.show_library => game.library.flip_horizontal(),
+                    KeyCode::Char('F') if game.show_library => game.library.flip_vertical(),
+                    KeyCode::Enter if game.show_library => {
+                        let (x, y) = game.centered_origin(&game.library.stamp);
+                        population.stamp(&game.library.stamp, x, y);
+                    }
+                    KeyCode::Enter => game.use_tool(&mut population),
+                    KeyCode::Left if shift => game.pan(-1, 0),
+                    KeyCode::Right if shift => game.pan(1, 0),
+                    KeyCode::Up if shift => game.pan(0, 1),
+                    KeyCode::Down if shift => game.pan(0, -1),
+                    KeyCode::Left => game.move_cursor(-1, 0),
+                    KeyCode::Right => game.move_cursor(1, 0),
+                    KeyCode::Up => game.move_cursor(0, 1),
+                    KeyCode::Down => game.move_cursor(0, -1),
+                    KeyCode::Char('m') => {
+                        game.view.renderer = game.view.renderer.next();
+                        game.view.zoom(0, game.cursor.x, game.cursor.y);
+                        game.status = Some(format!("renderer: {}", game.view.renderer.name()));
+                    }
+                    KeyCode::Char('i') => game.view.zoom(1, game.cursor.x, game.cursor.y),
+                    KeyCode::Char('o') => game.view.zoom(-1, game.cursor.x, game.cursor.y),
+                    KeyCode::Char('0') => if let Some(bounding_box) = population.bounding_box() {
+                        game.view.fit(bounding_box);
+                        game.cursor = Cursor {x: (bounding_box.0 + bounding_box.2) / 2, y: (bounding_box.1 + bounding_box.3) / 2};
+                    }
+                    KeyCode::Char('+') | KeyCode::Char('-') => {
+                        game.speed = match key.code {
+                            KeyCode::Char('+') => (game.speed + 1).min(SPEEDS.len()),
+                            _ => game.speed.saturating_sub(1),
+                        };
+                        speed_sample = (Instant::now(), population.generation);
+                        game.measured_speed = 0.0;
+                        game.status = Some(format!("speed: {}", game.describe_speed()));
+                    }
                     _ => {}
                 }
+
+                if let Some(before) = before {
+                    // a restored or loaded board starts its statistics and cycles afresh \\
+                    if reloaded {
+                        stats = Stats::new(&population);
+                        detector = CycleDetector::new();
+                        game.watch_cycles(&mut detector, &population);
+                        game.refresh_census(&population);
+                        game.activity.reset();
+                        game.track_activity(&population, false);
+                    }
+
+                    // statistics record \\
+                    if !reloaded && population.generation > before.generation {
+                        stats.record(&before, &population);
+                    } else if !reloaded && population.generation < before.generation {
+                        stats.rewind(population.generation);
+                    }
+
+                    // cycle detection \\
+                    if !reloaded && (population.generation != before.generation || !population.same_cells(&before)) {
+                        let detection = game.watch_cycles(&mut detector, &population);
+                        if population.generation > before.generation {
+                            game.check_breakpoints(&before, &population, detection);
+                        }
+                        game.refresh_census(&population);
+                        let stepped = population.generation == before.generation + 1;
+                        if !stepped && population.generation != before.generation {
+                            game.activity.reset();
+                        }
+                        game.track_activity(&population, stepped);
+                    }
+
+                    // history record \\
+                    let change = match key.code {
+                        KeyCode::Char('u') | KeyCode::Char('U') | KeyCode::Char('b') => None,
+                        KeyCode::Char('n') | KeyCode::Char('j') => Some(Change::Step),
+                        _ => Some(Change::Edit),
+                    };
+                    if let Some(change) = change {
+                        if !population.same_cells(&before) {
+                            history.record(change, before, &population);
+                        }
+                    }
+                }
             }
         }
 
         // grid update \\
-        if last_tick.elapsed() >= Duration::from_millis(frame_rate) {
+        if last_tick.elapsed() >= game.step_interval() {
             if !game.paused {
-                population.get_next_gen();
-                game.running_time += frame_rate as u16;
+                advance(&mut game, &mut population, &mut detector, &mut stats, &mut history, TURBO_BUDGET);
             }
             last_tick = Instant::now();
         }
+        if speed_sample.0.elapsed() >= Duration::from_secs(1) {
+            let gens = population.generation.saturating_sub(speed_sample.1);
+            game.measured_speed = gens as f64 / speed_sample.0.elapsed().as_secs_f64();
+            speed_sample = (Instant::now(), population.generation);
+        }
+    }
+}
+
+// turbo runs generations until the frame budget is spent; only cycles and
+// breakpoints are looked at every generation, the rest once per frame
+// against the generation the frame started from \\
+fn advance(game: &mut Game, population: &mut Population, detector: &mut CycleDetector, stats: &mut Stats, history: &mut History<Population>, budget: Duration) {
+    let frame_start = Instant::now();
+    let before = population.clone();
+    loop {
+        let previous = if game.compares_steps() {Some(population.clone())} else {None};
+        let start = Instant::now();
+        population.get_next_gen();
+        game.step_time = start.elapsed();
+        let detection = game.watch_cycles(detector, population);
+        game.check_breakpoints(previous.as_ref().unwrap_or(&before), population, detection);
+        if game.paused || !game.turbo() || frame_start.elapsed() >= budget {
+            break;
+        }
     }
+    stats.record(&before, population);
+    game.refresh_census(population);
+    game.track_activity(population, true);
+    history.record(Change::Step, before, population);
 }
 
-fn ui<B: Backend>(f: &mut Frame<B>, game: &Game, population: &Population) {
+// prints a glyph over every character covered by a cell \\
+fn paint_cell(ctx: &mut Context, view: &Viewport, x: i64, y: i64, glyph: &'static str, color: Color) {
+    if view.dotted() {
+        paint_population(ctx, view, &[(x, y)], color);
+    } else if let Some((sx, sy)) = view.to_screen(x, y) {
+        let k = if view.scale > 0 {view.factor()} else {1};
+        let width = k.min(view.cols - sx) as usize;
+        for dy in 0..k.min(view.rows - sy) {
+            ctx.print(sx as f64, (sy + dy) as f64, Span::styled(glyph.repeat(width), Style::default().fg(color)));
+        }
+    }
+}
+
+// live cells, as dots or shaded by density when several cells share a character \\
+fn paint_population(ctx: &mut Context, view: &Viewport, cells: &[(i64, i64)], color: Color) {
+    if view.dotted() {
+        let k = view.factor();
+        let dots = cells.iter()
+            .filter(|&&(x, y)| view.contains(x, y))
+            .map(|&(x, y)| ((x - view.x) / k, (y - view.y) / k));
+        for ((sx, sy), mask) in view.renderer.rasterize(dots) {
+            if sx < view.cols && sy < view.rows {
+                ctx.print(sx as f64, sy as f64, Span::styled(view.renderer.glyph(mask), Style::default().fg(color)));
+            }
+        }
+        return;
+    }
+    if view.scale >= 0 {
+        for &(x, y) in cells {
+            paint_cell(ctx, view, x, y, "█", color);
+        }
+        return;
+    }
+    let mut counts: HashMap<(i64, i64), i64> = HashMap::new();
+    for &(x, y) in cells {
+        if let Some(char_position) = view.to_screen(x, y) {
+            *counts.entry(char_position).or_insert(0) += 1;
+        }
+    }
+    let area = view.factor() * view.factor();
+    for ((sx, sy), count) in counts {
+        let glyph = match count * 4 / area {
+            0 => "░",
+            1 => "▒",
+            2 => "▓",
+            _ => "█",
+        };
+        ctx.print(sx as f64, sy as f64, Span::styled(glyph, Style::default().fg(color)));
+    }
+}
+
+// live cells colored according to the shading mode \\
+fn paint_shaded(ctx: &mut Context, game: &Game, population: &Population, (x0, y0, x1, y1): (i64, i64, i64, i64)) {
+    let (view, theme, activity) = (&game.view, game.theme(), &game.activity);
+    let visible = population.cells_in(x0, y0, x1, y1);
+    let in_view = |&&(x, y): &&(i64, i64)| x >= x0 && y >= y0 && x <= x1 && y <= y1;
+    match game.shading {
+        Shading::Plain | Shading::History => paint_population(ctx, view, &visible, theme.live),
+        Shading::Age => {
+            let mut buckets: [Vec<(i64, i64)>; 6] = Default::default();
+            for cell in visible {
+                let age = activity.ages.get(&cell).copied().unwrap_or(0);
+                buckets[((age + 1).ilog2() as usize).min(5)].push(cell);
+            }
+            for (cells, color) in buckets.iter().zip(theme.age) {
+                paint_population(ctx, view, cells, color);
+            }
+        }
+        Shading::Heat => {
+            paint_population(ctx, view, &visible, theme.live);
+            let mut buckets: [Vec<(i64, i64)>; 6] = Default::default();
+            for (cell, count) in activity.heat.iter().filter(|(cell, _)| in_view(cell)) {
+                buckets[((*count as usize - 1) * 6 / HEAT_SPAN).min(5)].push(*cell);
+            }
+            for (cells, color) in buckets.iter().zip(theme.heat) {
+                paint_population(ctx, view, cells, color);
+            }
+        }
+        Shading::Flash => {
+            let deaths: Vec<(i64, i64)> = activity.deaths.iter().filter(in_view).copied().collect();
+            let births: Vec<(i64, i64)> = activity.births.iter().filter(in_view).copied().collect();
+            paint_population(ctx, view, &deaths, theme.death);
+            paint_population(ctx, view, &visible, theme.live);
+            paint_population(ctx, view, &births, theme.birth);
+        }
+    }
+}
+
+fn ui<B: Backend>(f: &mut Frame<B>, game: &Game, population: &Population, stats: &Stats) {
+    let graph_height = if game.show_graph {GRAPH_HEIGHT} else {0};
     let chunks = Layout::default()
-        .constraints([Constraint::Length(3), Constraint::Min(2)])
+        .constraints([Constraint::Length(3), Constraint::Min(2), Constraint::Length(graph_height)])
         .split(f.size());
     
     // controls information \\
-    let paragraph = Paragraph::new(HELP)
+    let keys = if game.selection.is_some() {SELECTION_HELP} else {HELP};
+    let help = match &game.prompt {
+        Some(prompt) => format!("{} {}_    {}", prompt.question(), prompt.text, PROMPT_HELP),
+        None => help_line(keys, chunks[0].width.saturating_sub(2) as usize),
+    };
+    let paragraph = Paragraph::new(help)
         .block(Block::default().title("[Help]").borders(Borders::ALL));
     f.render_widget(paragraph, chunks[0]);
     
     // canvas \\
+    let view = &game.view;
     let pause_span = if game.paused {Span::styled("paused", Style::default().fg(Color::Red))}
     else {Span::styled("playing", Style::default().fg(Color::Green))};
-    let title = vec![
+    let mut zoom = if view.scale < 0 {format!("1:{}", view.factor())} else {format!("{}:1", view.factor())};
+    if view.dotted() {
+        zoom = format!("{} {}", zoom, view.renderer.name());
+    }
+    let (births, deaths) = match stats.last() {
+        Some(sample) if sample.generation == population.generation => (sample.births, sample.deaths),
+        _ => (0, 0),
+    };
+    let mut title = vec![
         Span::raw("[Game of Life: "),
         pause_span,
-        Span::raw(format!("| Timer: {}]", game.running_time/1000))
+        Span::raw(format!("| Generation: {} | Population: {} | Births: {} | Deaths: {}", population.generation, population.count(), births, deaths)),
+        Span::raw(format!(" | Speed: {} | Zoom: {} | Engine: {}", game.describe_speed(), zoom, population.engine_name())),
+        Span::raw(if population.rule.is_conway() {String::new()} else {format!(" | Rule: {}", population.rule)}),
+        Span::raw(if population.size.is_none() {String::new()} else {format!(" | Board: {}", describe_board(population.size))}),
+        Span::raw(if game.breakpoints.is_empty() {String::new()} else {format!(" | Breakpoints: {}", game.breakpoints.len())}),
+        Span::raw(if population.is_hashlife() {format!(" | Jump: 2^{}]", game.jump_exponent)} else {String::from("]")}),
     ];
+    let detected = game.detection.map(|detection| detection.describe());
+    if let Some(detected) = &detected {
+        title.push(Span::styled(format!("[{}]", detected), Style::default().fg(Color::Magenta)));
+    }
+    if let Some(status) = game.status.as_ref().filter(|&status| Some(status) != detected.as_ref()) {
+        title.push(Span::styled(format!("[{}]", status), Style::default().fg(Color::Yellow)));
+    }
     let canvas = Canvas::default()
         .block(Block::default().title(title).borders(Borders::ALL))
-        .x_bounds([0.0, game.cols])
-        .y_bounds([0.0, game.rows])
+        .x_bounds([0.0, (view.cols - 1) as f64])
+        .y_bounds([0.0, (view.rows - 1) as f64])
         .marker(symbols::Marker::Block)
         .paint(|ctx| {
-            if game.show_history {
-                for (x, y) in population.ghost_generation.clone() {
-                    ctx.print(x, y, Span::styled("█", Style::default().fg(Color::Rgb(0, 50, 50))))
+            let (w, h) = view.span();
+            let (x0, y0, x1, y1) = (view.x, view.y, view.x + w - 1, view.y + h - 1);
+            let theme = game.theme();
+            if let Some((cols, rows)) = population.size {
+                for sy in 0..view.rows {
+                    for sx in 0..view.cols {
+                        let (cx0, cy0, cx1, cy1) = view.to_world(sx, sy);
+                        if cx1 < 0 || cy1 < 0 || cx0 >= cols || cy0 >= rows {
+                            ctx.print(sx as f64, sy as f64, Span::styled("█", Style::default().fg(theme.outside)))
+                        }
+                    }
+                }
+            }
+            if game.shading == Shading::History {
+                for age in [2, 1] {
+                    let cells = population.previous_cells_in(age, x0, y0, x1, y1);
+                    paint_population(ctx, view, &cells, theme.history[age - 1]);
                 }
-                for (x, y) in population.dying_generation.clone() {
-                    ctx.print(x, y, Span::styled("█", Style::default().fg(Color::Rgb(0, 100, 100))))
+            }
+            if let Some((x0, y0, x1, y1)) = game.selection_rect() {
+                for sy in 0..view.rows {
+                    for sx in 0..view.cols {
+                        let (cx0, cy0, cx1, cy1) = view.to_world(sx, sy);
+                        if cx1 >= x0 && cy1 >= y0 && cx0 <= x1 && cy0 <= y1 {
+                            ctx.print(sx as f64, sy as f64, Span::styled("█", Style::default().fg(theme.selection)))
+                        }
+                    }
                 }
             }
-            for y in 0..(game.rows as usize) {
-                for x in 0..(game.cols as usize) {
-                    if population.current_generation[y][x] == 1 {
-                        ctx.print(x as f64, y as f64, Span::styled("█", Style::default().fg(Color::Rgb(0, 255, 255))))
+            match &game.census {
+                Some(census) => for object in &census.objects {
+                    let (ox0, oy0, ox1, oy1) = object.bounds;
+                    if ox1 >= x0 && oy1 >= y0 && ox0 <= x1 && oy0 <= y1 {
+                        let color = if object.kind == Kind::Unknown {theme.live} else {object.kind.color()};
+                        paint_population(ctx, view, &object.cells, color);
                     }
                 }
+                None => paint_shaded(ctx, game, population, (x0, y0, x1, y1)),
+            }
+            if game.show_library {
+                let (ox, oy) = game.centered_origin(&game.library.stamp);
+                for (x, y) in game.library.stamp.live_cells() {
+                    paint_cell(ctx, view, ox + x, oy + y, "█", theme.ghost);
+                }
             }
-            ctx.print(game.cursor.x as f64, game.cursor.y as f64, Span::styled("█", Style::default().fg(Color::White)))
+            paint_population(ctx, view, &game.tool_preview((x0, y0, x1, y1)), theme.ghost);
+            paint_cell(ctx, view, game.cursor.x, game.cursor.y, "█", theme.cursor);
         });
     f.render_widget(canvas, chunks[1]);
+    coordinates_ui(f, game, chunks[1]);
+
+    if game.show_library {
+        library_ui(f, game, chunks[1]);
+    }
+    if game.show_snapshots {
+        snapshots_ui(f, game, chunks[1]);
+    }
+    if game.show_stats {
+        stats_ui(f, game, population, chunks[1]);
+    }
+    if let Some(census) = &game.census {
+        let right = match (game.show_library, game.show_snapshots) {
+            (true, _) => LIBRARY_WIDTH,
+            (_, true) => SNAPSHOTS_WIDTH,
+            _ => 0,
+        };
+        census_ui(f, census, chunks[1], right);
+    }
+    if game.show_help {
+        help_ui(f, keys, chunks[1]);
+    }
+
+    // population graph \\
+    if game.show_graph {
+        let populations = stats.populations(chunks[2].width.saturating_sub(2) as usize);
+        let max = populations.iter().copied().max().unwrap_or(0);
+        let sparkline = Sparkline::default()
+            .block(Block::default().title(format!("[Population: max {}]", max)).borders(Borders::ALL))
+            .data(&populations)
+            .style(Style::default().fg(game.theme().live));
+        f.render_widget(sparkline, chunks[2]);
+    }
+}
+
+// the bindings of a help text, one per key \\
+fn help_bindings(help: &str) -> Vec<String> {
+    help.split(", [")
+        .enumerate()
+        .map(|(idx, binding)| if idx == 0 {String::from(binding)} else {format!("[{}", binding)})
+        .collect()
+}
+
+// as many bindings as fit the width, pointing to [?] for the rest \\
+fn help_line(help: &str, width: usize) -> String {
+    if help.chars().count() <= width {
+        return String::from(help);
+    }
+    let mut line = String::new();
+    for binding in help_bindings(help) {
+        if line.chars().count() + binding.chars().count() + MORE_HELP.len() + 2 > width {
+            break;
+        }
+        line.push_str(&binding);
+        line.push_str(", ");
+    }
+    line + MORE_HELP
+}
+
+// every binding of a help text in columns, over the middle of the canvas \\
+fn help_ui<B: Backend>(f: &mut Frame<B>, help: &str, canvas_area: Rect) {
+    let bindings = help_bindings(help);
+    let column = bindings.iter().map(|binding| binding.chars().count()).max().unwrap_or(0) + 2;
+    let columns = (canvas_area.width.saturating_sub(2) as usize / column).max(1);
+    let rows = bindings.len().div_ceil(columns);
+    let lines: Vec<Spans> = (0..rows)
+        .map(|row| {
+            let line: String = (row..bindings.len()).step_by(rows)
+                .map(|idx| format!("{:<width$}", bindings[idx], width = column))
+                .collect();
+            Spans::from(line)
+        })
+        .collect();
+
+    let width = ((column * columns.min(bindings.len())) as u16 + 2).min(canvas_area.width);
+    let height = (rows as u16 + 2).min(canvas_area.height);
+    let area = Rect::new(
+        canvas_area.x + (canvas_area.width - width) / 2,
+        canvas_area.y + (canvas_area.height - height) / 2,
+        width,
+        height,
+    );
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().title("[Keys: [?] to close]").borders(Borders::ALL));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// engine statistics drawn over the top left corner of the canvas \\
+fn stats_ui<B: Backend>(f: &mut Frame<B>, game: &Game, population: &Population, canvas_area: Rect) {
+    let engine = match &population.engine {
+        Engine::Packed(grid) => format!("packed, {} of {} thread(s)", grid.effective_threads(population.threads), population.threads),
+        Engine::HashLife(_) => String::from("hashlife"),
+    };
+    let mut lines = vec![
+        Spans::from(format!("Engine: {}", engine)),
+        Spans::from(format!("Step: {:.3} ms", game.step_time.as_secs_f64() * 1000.0)),
+        Spans::from(format!("Soup seed: {}", game.soup.seed)),
+    ];
+    if let Some(tiles) = population.tile_count() {
+        lines.push(Spans::from(format!("Tiles: {}", tiles)));
+    }
+
+    let width = 32.min(canvas_area.width.saturating_sub(2));
+    let height = (lines.len() as u16 + 2).min(canvas_area.height.saturating_sub(2));
+    let area = Rect::new(canvas_area.x + 1, canvas_area.y + 1, width, height);
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().title("[Stats]").borders(Borders::ALL));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// object counts drawn over the right side of the canvas, left of the library if shown \\
+fn census_ui<B: Backend>(f: &mut Frame<B>, census: &Census, canvas_area: Rect, right: u16) {
+    let width = CENSUS_WIDTH.min(canvas_area.width.saturating_sub(right));
+    let area = Rect::new(canvas_area.right().saturating_sub(right + width), canvas_area.y, width, canvas_area.height);
+
+    let mut items: Vec<ListItem> = Vec::new();
+    for (kind, name, count) in census.counts() {
+        items.push(ListItem::new(Span::styled(format!("{:<20}{:>6}", name, count), Style::default().fg(kind.color()))));
+    }
+    let unknown: Vec<_> = census.unknown().collect();
+    if !unknown.is_empty() {
+        items.push(ListItem::new(Span::styled(format!("{:<20}{:>6}", "Unknown", unknown.len()), Style::default().fg(Color::DarkGray))));
+    }
+    for object in unknown {
+        let (x0, y0, x1, y1) = object.bounds;
+        items.push(ListItem::new(Span::styled(format!(" ({},{})-({},{})", x0, y0, x1, y1), Style::default().fg(Color::DarkGray))));
+    }
+
+    let list = List::new(items)
+        .block(Block::default().title(format!("[Census of generation {}: {} objects]", census.generation, census.objects.len())).borders(Borders::ALL));
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+// snapshots taken so far, listed over the right side of the canvas \\
+fn snapshots_ui<B: Backend>(f: &mut Frame<B>, game: &Game, canvas_area: Rect) {
+    let width = SNAPSHOTS_WIDTH.min(canvas_area.width);
+    let area = Rect::new(canvas_area.right() - width, canvas_area.y, width, canvas_area.height);
+
+    let mut items: Vec<ListItem> = Vec::new();
+    for snapshot in &game.snapshots.list {
+        items.push(ListItem::new(vec![
+            Spans::from(format!(" {}", snapshot.name)),
+            Spans::from(Span::styled(
+                format!("   gen {}, pop {}", snapshot.population.generation, snapshot.population.count()),
+                Style::default().fg(Color::DarkGray),
+            )),
+        ]));
+    }
+    if items.is_empty() {
+        items.push(ListItem::new(Span::styled("no snapshots yet", Style::default().fg(Color::DarkGray))));
+    }
+    items.push(ListItem::new(" "));
+    for hint in SNAPSHOTS_HELP {
+        items.push(ListItem::new(Span::styled(hint, Style::default().fg(Color::DarkGray))));
+    }
+
+    let mut list_state = ListState::default();
+    list_state.select(game.snapshots.selected().map(|_| game.snapshots.selected));
+    let list = List::new(items)
+        .block(Block::default().title("[Snapshots]").borders(Borders::ALL))
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Cyan))
+        .highlight_symbol(">");
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+// world coordinates of the visible window and of the cursor, over the bottom border of the canvas \\
+fn coordinates_ui<B: Backend>(f: &mut Frame<B>, game: &Game, canvas_area: Rect) {
+    let (w, h) = game.view.span();
+    let text = format!(
+        " x: {}..{} | y: {}..{} | cursor: ({}, {}) ",
+        game.view.x, game.view.x + w - 1, game.view.y, game.view.y + h - 1, game.cursor.x, game.cursor.y,
+    );
+    let width = (text.len() as u16).min(canvas_area.width.saturating_sub(2));
+    if width == 0 || canvas_area.height < 2 {
+        return;
+    }
+    let area = Rect::new(canvas_area.x + 1, canvas_area.bottom() - 1, width, 1);
+    f.render_widget(Paragraph::new(Span::styled(text, Style::default().fg(Color::DarkGray))), area);
+}
+
+// pattern palette drawn over the right side of the canvas \\
+fn library_ui<B: Backend>(f: &mut Frame<B>, game: &Game, canvas_area: Rect) {
+    let width = LIBRARY_WIDTH.min(canvas_area.width);
+    let area = Rect::new(canvas_area.right() - width, canvas_area.y, width, canvas_area.height);
+
+    let mut items: Vec<ListItem> = Vec::new();
+    let mut selected = 0;
+    for category in CATEGORIES {
+        items.push(ListItem::new(Span::styled(category, Style::default().fg(Color::DarkGray))));
+        for (idx, (_, name, _)) in PATTERNS.iter().enumerate().filter(|(_, p)| p.0 == category) {
+            if idx == game.library.selected {
+                selected = items.len();
+            }
+            items.push(ListItem::new(Spans::from(format!(" {}", name))));
+        }
+    }
+    items.push(ListItem::new(" "));
+    for hint in LIBRARY_HELP {
+        items.push(ListItem::new(Span::styled(hint, Style::default().fg(Color::DarkGray))));
+    }
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(selected));
+    let list = List::new(items)
+        .block(Block::default().title("[Patterns]").borders(Borders::ALL))
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Cyan))
+        .highlight_symbol(">");
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn population(cells: &[(i64, i64)]) -> Population {
+        let mut population = Population::new();
+        for &(x, y) in cells {
+            population.set(x, y, true);
+        }
+        population
+    }
+
+    fn sorted(mut cells: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn resizing_moves_patterns_onto_the_board() {
+        // a glider left of and below the board \\
+        let glider = population(&[(-10, -10), (-9, -11), (-11, -12), (-10, -12), (-9, -12)]);
+        let (board, clipped) = glider.resized(Some((20, 10)));
+        assert_eq!(clipped, 0);
+        assert_eq!(sorted(board.live_cells()), vec![(0, 0), (1, 0), (1, 2), (2, 0), (2, 1)]);
+
+        // cells already on the board stay put \\
+        let (again, clipped) = board.resized(Some((30, 30)));
+        assert_eq!(clipped, 0);
+        assert!(again.same_cells(&board));
+
+        let (unbounded, clipped) = glider.resized(None);
+        assert_eq!(clipped, 0);
+        assert!(unbounded.same_cells(&glider));
+    }
+
+    #[test]
+    fn resizing_counts_the_cells_left_out() {
+        // a row of ten cells centered on a board four cells wide \\
+        let row = population(&(0..10).map(|x| (x, 0)).collect::<Vec<_>>());
+        let (board, clipped) = row.resized(Some((4, 1)));
+        assert_eq!(clipped, 6);
+        assert_eq!(sorted(board.live_cells()), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(fit_offset(0, 9, 4), -3);
+    }
+
+    #[test]
+    fn help_lines_point_to_the_full_list() {
+        let help = "[s]: 'swap cell state', [[/]]: 'jump size', [</>]: 'soup density'";
+        assert_eq!(help_bindings(help), vec!["[s]: 'swap cell state'", "[[/]]: 'jump size'", "[</>]: 'soup density'"]);
+        assert_eq!(help_line(help, 80), help);
+        assert_eq!(help_line(help, 60), format!("[s]: 'swap cell state', [[/]]: 'jump size', {}", MORE_HELP));
+        assert_eq!(help_line(help, 20), MORE_HELP);
+        assert!(help_line(HELP, 120).chars().count() <= 120);
+    }
+
+    #[test]
+    fn parses_image_exports() {
+        assert_eq!(parse_export(""), Some((String::from(IMAGE_FILE), EXPORT_CELL)));
+        assert_eq!(parse_export(" glider.pbm "), Some((String::from("glider.pbm"), EXPORT_CELL)));
+        assert_eq!(parse_export("glider.png 3"), Some((String::from("glider.png"), 3)));
+        assert_eq!(parse_export("glider.png 0"), None);
+        assert_eq!(parse_export("glider.png 65"), None);
+        assert_eq!(parse_export("glider.png 3 4"), None);
+    }
+
+    #[test]
+    fn rotating_a_wide_selection_replaces_the_cells_it_lands_on() {
+        // a 3x1 row with a gap, and a cell above it that lands in the gap once rotated \\
+        let mut population = population(&[(0, 0), (2, 0), (0, 1)]);
+        let mut game = Game::new(40, 20);
+        game.selection = Some(Cursor {x: 0, y: 0});
+        game.cursor = Cursor {x: 2, y: 0};
+        game.transform_selection(&mut population, Population::rotated);
+        assert_eq!(sorted(population.live_cells()), vec![(0, 0), (0, 2)]);
+        assert_eq!(game.selection_rect(), Some((0, 0, 0, 2)));
+    }
+
+    #[test]
+    fn steps_are_kept_as_the_cells_they_flip() {
+        let glider = population(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        let mut population = glider.clone();
+        let mut history = History::new();
+        for _ in 0..8 {
+            let before = population.clone();
+            population.get_next_gen();
+            assert!(population.diff(&before).is_some());
+            history.record(Change::Step, before, &population);
+        }
+        let after = population.clone();
+        for _ in 0..8 {
+            history.step_back(&mut population);
+        }
+        assert!(population.same_cells(&glider));
+        assert_eq!(population.generation, 0);
+        for _ in 0..8 {
+            history.redo(&mut population);
+        }
+        assert!(population.same_cells(&after));
+        assert_eq!(population.generation, 8);
+
+        // a new board size is kept whole \\
+        let (board, _) = population.resized(Some((10, 10)));
+        assert!(board.diff(&population).is_none());
+    }
+
+    #[test]
+    fn soups_fill_the_whole_board() {
+        // a board larger than the view \\
+        let mut game = Game::new(40, 20);
+        game.soup.seed = 1;
+        let mut population = Population::new().resized(Some((100, 60))).0;
+        game.fill_soup(&mut population);
+        assert_eq!(population.bounding_box(), Some((0, 0, 99, 59)));
+
+        // a selection still limits the soup \\
+        let mut population = Population::new().resized(Some((100, 60))).0;
+        game.selection = Some(Cursor {x: 10, y: 10});
+        game.cursor = Cursor {x: 19, y: 14};
+        game.fill_soup(&mut population);
+        let (x0, y0, x1, y1) = population.bounding_box().unwrap();
+        assert!(x0 >= 10 && y0 >= 10 && x1 <= 19 && y1 <= 14);
+    }
+
+    #[test]
+    fn turbo_frames_are_recorded_once() {
+        // a glider run in turbo up to a generation target \\
+        let glider = population(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        let mut population = glider.clone();
+        let mut game = Game::new(40, 20);
+        game.speed = SPEEDS.len();
+        game.add_breakpoint(Breakpoint::Generation(40));
+        let (mut detector, mut stats, mut history) = (CycleDetector::new(), Stats::new(&population), History::new());
+        advance(&mut game, &mut population, &mut detector, &mut stats, &mut history, Duration::from_secs(60));
+        assert!(game.paused);
+        assert_eq!(population.generation, 40);
+
+        // one sample and one history entry for the whole frame \\
+        assert_eq!(stats.populations(usize::MAX), vec![5, 5]);
+        assert_eq!(stats.last().map(|sample| sample.generation), Some(40));
+        history.step_back(&mut population);
+        assert!(population.same_cells(&glider));
+        assert_eq!(population.generation, 0);
+    }
 }
@@ -0,0 +1,104 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use super::Population;
+
+// Per-cell bookkeeping for the shading modes that need more than the two
+// generations the packed engine keeps: how long each live cell has been
+// alive, how often each cell changed state over the last HEAT_SPAN
+// generations, and what was born or died on the last step. It is only kept
+// up to date while one of those modes is shown.
+
+pub const HEAT_SPAN: usize = 32;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shading {
+    Plain,
+    History,
+    Age,
+    Heat,
+    Flash,
+}
+
+impl Shading {
+    const ALL: [Shading; 5] = [Shading::Plain, Shading::History, Shading::Age, Shading::Heat, Shading::Flash];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shading::Plain => "plain",
+            Shading::History => "history",
+            Shading::Age => "age",
+            Shading::Heat => "heat",
+            Shading::Flash => "flash",
+        }
+    }
+
+    pub fn next(self) -> Shading {
+        let idx = Shading::ALL.iter().position(|s| *s == self).unwrap();
+        Shading::ALL[(idx + 1) % Shading::ALL.len()]
+    }
+
+    pub fn needs_activity(self) -> bool {
+        matches!(self, Shading::Age | Shading::Heat | Shading::Flash)
+    }
+}
+
+
+pub struct Activity {
+    pub ages: HashMap<(i64, i64), u64>,
+    pub heat: HashMap<(i64, i64), u32>,
+    changes: VecDeque<Vec<(i64, i64)>>,
+    pub births: Vec<(i64, i64)>,
+    pub deaths: Vec<(i64, i64)>,
+}
+
+impl Activity {
+    pub fn new() -> Activity {
+        Activity {
+            ages: HashMap::new(),
+            heat: HashMap::new(),
+            changes: VecDeque::new(),
+            births: Vec::new(),
+            deaths: Vec::new(),
+        }
+    }
+
+    // catches up with the population, aging cells and counting changes only for a step \\
+    pub fn update(&mut self, population: &Population, stepped: bool) {
+        let alive: HashSet<(i64, i64)> = population.live_cells().into_iter().collect();
+        let births: Vec<(i64, i64)> = alive.iter().copied().filter(|cell| !self.ages.contains_key(cell)).collect();
+        let deaths: Vec<(i64, i64)> = self.ages.keys().copied().filter(|cell| !alive.contains(cell)).collect();
+
+        self.ages.retain(|cell, _| alive.contains(cell));
+        if stepped {
+            self.ages.values_mut().for_each(|age| *age += 1);
+        }
+        self.ages.extend(births.iter().map(|&cell| (cell, 0)));
+
+        if stepped {
+            let changed: Vec<(i64, i64)> = births.iter().chain(&deaths).copied().collect();
+            for cell in &changed {
+                *self.heat.entry(*cell).or_insert(0) += 1;
+            }
+            self.changes.push_back(changed);
+            if self.changes.len() > HEAT_SPAN {
+                for cell in self.changes.pop_front().unwrap() {
+                    if let Some(count) = self.heat.get_mut(&cell) {
+                        *count -= 1;
+                        if *count == 0 {
+                            self.heat.remove(&cell);
+                        }
+                    }
+                }
+            }
+            self.births = births;
+            self.deaths = deaths;
+        } else {
+            self.births.clear();
+            self.deaths.clear();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Activity::new();
+    }
+}
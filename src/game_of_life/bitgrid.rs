@@ -0,0 +1,439 @@
+use std::{collections::HashMap, mem, thread};
+use super::rules::Rule;
+
+// The plane is cut into 64x64 tiles, one u64 per row with bit x holding
+// the cell at column x, so a whole row of neighbor counts is computed with
+// a handful of bitwise operations. Generations rotate through a fixed set
+// of buffers: stepping writes into `next` and the older buffers are reused,
+// which also keeps the two previous generations around for free. Tiles are
+// only kept while they hold live cells.
+//
+// With several threads, the tiles to compute are sorted into horizontal
+// bands, one per thread, all reading the same current generation. Every
+// tile goes through the same `step_tile`, so the result does not depend on
+// the number of threads.
+
+pub const TILE: i64 = 64;
+const EMPTY: [u64; 64] = [0; 64];
+const MIN_TILES_PER_THREAD: usize = 16;
+
+type Tiles = HashMap<(i64, i64), [u64; 64]>;
+type Band = Vec<((i64, i64), [u64; 64])>;
+
+
+#[derive(Clone, Default)]
+pub struct BitGrid {
+    current: Tiles,
+    previous: Tiles,
+    older: Tiles,
+    next: Tiles,
+    candidates: Vec<(i64, i64)>,
+    bands: Vec<Band>,
+}
+
+
+fn tile_of(x: i64, y: i64) -> ((i64, i64), usize, usize) {
+    ((x.div_euclid(TILE), y.div_euclid(TILE)), x.rem_euclid(TILE) as usize, y.rem_euclid(TILE) as usize)
+}
+
+fn half_add(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    (a ^ b ^ c, (a & b) | (c & (a ^ b)))
+}
+
+// next state of a row given its eight neighbor words, bit-sliced counting \\
+fn next_row(alive: u64, n: [u64; 8], rule: Rule) -> u64 {
+    let (a1, a2) = full_add(n[0], n[1], n[2]);
+    let (b1, b2) = full_add(n[3], n[4], n[5]);
+    let (c1, c2) = half_add(n[6], n[7]);
+    let (ones, d2) = full_add(a1, b1, c1);
+    let (e2, e4) = full_add(a2, b2, c2);
+    let (twos, f4) = half_add(e2, d2);
+    let fours = e4 ^ f4;
+    let eights = e4 & f4;
+
+    // two neighbors keep a cell alive, three also give birth \\
+    if rule.is_conway() {
+        return twos & !fours & !eights & (ones | alive);
+    }
+
+    // otherwise every neighbor count the rule cares about is matched bit by bit \\
+    let mut next = 0;
+    for count in 0..=8 {
+        let (born, survives) = (rule.birth >> count & 1 == 1, rule.survival >> count & 1 == 1);
+        let outcome = match (born, survives) {
+            (false, false) => continue,
+            (true, true) => u64::MAX,
+            (true, false) => !alive,
+            (false, true) => alive,
+        };
+        let bit = |word: u64, place: u32| if count >> place & 1 == 1 {word} else {!word};
+        next |= bit(ones, 0) & bit(twos, 1) & bit(fours, 2) & bit(eights, 3) & outcome;
+    }
+    next
+}
+
+fn step_tile(tiles: &Tiles, tx: i64, ty: i64, rule: Rule) -> [u64; 64] {
+    let get = |dx: i64, dy: i64| tiles.get(&(tx + dx, ty + dy)).unwrap_or(&EMPTY);
+    let (center, left, right) = (get(0, 0), get(-1, 0), get(1, 0));
+    let (up, up_left, up_right) = (get(0, 1), get(-1, 1), get(1, 1));
+    let (down, down_left, down_right) = (get(0, -1), get(-1, -1), get(1, -1));
+
+    // (row, row of the left tile, row of the right tile) \\
+    let row = |y: i64| match y {
+        -1 => (down[63], down_left[63], down_right[63]),
+        64 => (up[0], up_left[0], up_right[0]),
+        y => (center[y as usize], left[y as usize], right[y as usize]),
+    };
+    // (west neighbors, east neighbors) shifted in line with the row \\
+    let shifted = |(mid, left, right): (u64, u64, u64)| ((mid << 1) | (left >> 63), (mid >> 1) | (right << 63));
+
+    let mut next = EMPTY;
+    for (y, next_row_bits) in next.iter_mut().enumerate() {
+        let y = y as i64;
+        let (below, same, above) = (row(y - 1), row(y), row(y + 1));
+        let (below_w, below_e) = shifted(below);
+        let (same_w, same_e) = shifted(same);
+        let (above_w, above_e) = shifted(above);
+        *next_row_bits = next_row(same.0, [below_w, below.0, below_e, same_w, same_e, above_w, above.0, above_e], rule);
+    }
+    next
+}
+
+
+impl BitGrid {
+    pub fn new() -> BitGrid {
+        BitGrid::default()
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        let (key, bx, by) = tile_of(x, y);
+        self.current.get(&key).is_some_and(|tile| tile[by] >> bx & 1 == 1)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        let (key, bx, by) = tile_of(x, y);
+        if alive {
+            self.current.entry(key).or_insert(EMPTY)[by] |= 1 << bx;
+        } else if let Some(tile) = self.current.get_mut(&key) {
+            tile[by] &= !(1 << bx);
+            if tile.iter().all(|row| *row == 0) {
+                self.current.remove(&key);
+            }
+        }
+    }
+
+    pub fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+        for (&(tx, ty), tile) in &self.current {
+            let columns = tile.iter().fold(0, |acc, row| acc | row);
+            let y0 = tile.iter().position(|row| *row != 0).unwrap() as i64;
+            let y1 = tile.iter().rposition(|row| *row != 0).unwrap() as i64;
+            let x0 = columns.trailing_zeros() as i64;
+            let x1 = 63 - columns.leading_zeros() as i64;
+            let (x0, y0, x1, y1) = (tx * TILE + x0, ty * TILE + y0, tx * TILE + x1, ty * TILE + y1);
+            bounds = Some(match bounds {
+                None => (x0, y0, x1, y1),
+                Some((a0, b0, a1, b1)) => (a0.min(x0), b0.min(y0), a1.max(x1), b1.max(y1)),
+            });
+        }
+        bounds
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (&(i64, i64), &[u64; 64])> {
+        self.current.iter()
+    }
+
+    // live cells of this generation (age 0) or of one of the two before it \\
+    pub fn cells_in(&self, age: usize, x0: i64, y0: i64, x1: i64, y1: i64) -> Vec<(i64, i64)> {
+        let tiles = match age {
+            0 => &self.current,
+            1 => &self.previous,
+            _ => &self.older,
+        };
+        let mut cells = Vec::new();
+        for (&(tx, ty), tile) in tiles {
+            let (ox, oy) = (tx * TILE, ty * TILE);
+            if ox > x1 || oy > y1 || ox + TILE <= x0 || oy + TILE <= y0 {
+                continue;
+            }
+            for (by, row) in tile.iter().enumerate() {
+                let y = oy + by as i64;
+                let mut bits = *row;
+                while bits != 0 && y >= y0 && y <= y1 {
+                    let x = ox + bits.trailing_zeros() as i64;
+                    bits &= bits - 1;
+                    if x >= x0 && x <= x1 {
+                        cells.push((x, y));
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    pub fn clear_region(&mut self, x0: i64, y0: i64, x1: i64, y1: i64) {
+        self.current.retain(|&(tx, ty), tile| {
+            let (ox, oy) = (tx * TILE, ty * TILE);
+            let lo = (x0 - ox).clamp(0, TILE);
+            let hi = (x1 + 1 - ox).clamp(0, TILE);
+            let mask = if hi <= lo {0} else if hi - lo == TILE {u64::MAX} else {((1u64 << (hi - lo)) - 1) << lo};
+            for (by, row) in tile.iter_mut().enumerate() {
+                let y = oy + by as i64;
+                if y >= y0 && y <= y1 {
+                    *row &= !mask;
+                }
+            }
+            tile.iter().any(|row| *row != 0)
+        });
+    }
+
+    pub fn same_cells(&self, other: &BitGrid) -> bool {
+        self.current == other.current
+    }
+
+    pub fn count(&self) -> u64 {
+        self.current.values().flatten().map(|row| row.count_ones() as u64).sum()
+    }
+
+    // cells alive here but not in `before` \\
+    pub fn new_cells(&self, before: &BitGrid) -> u64 {
+        let mut count = 0;
+        for (key, tile) in &self.current {
+            let old = before.current.get(key).unwrap_or(&EMPTY);
+            count += tile.iter().zip(old).map(|(row, old)| (row & !old).count_ones() as u64).sum::<u64>();
+        }
+        count
+    }
+
+    // tiles that differ from `before`, as the rows that flip between the two \\
+    pub fn changes(&self, before: &BitGrid) -> Vec<((i64, i64), [u64; 64])> {
+        let mut changes = Vec::new();
+        for (key, tile) in &self.current {
+            let old = before.current.get(key).unwrap_or(&EMPTY);
+            if tile != old {
+                changes.push((*key, std::array::from_fn(|y| tile[y] ^ old[y])));
+            }
+        }
+        for (key, tile) in &before.current {
+            if !self.current.contains_key(key) {
+                changes.push((*key, *tile));
+            }
+        }
+        changes
+    }
+
+    // flips the rows given by `changes`, going either way between the two
+    // generations they were taken from; the older generations kept for
+    // shading no longer lead here, so they are dropped \\
+    pub fn flip(&mut self, changes: &[((i64, i64), [u64; 64])]) {
+        for (key, rows) in changes {
+            let tile = self.current.entry(*key).or_insert(EMPTY);
+            for (row, flipped) in tile.iter_mut().zip(rows) {
+                *row ^= flipped;
+            }
+            if tile.iter().all(|row| *row == 0) {
+                self.current.remove(key);
+            }
+        }
+        self.previous.clear();
+        self.older.clear();
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.current.len()
+    }
+
+    // bytes held by the tile buffers of all kept generations \\
+    pub fn footprint(&self) -> usize {
+        let tiles: usize = [&self.current, &self.previous, &self.older, &self.next].iter().map(|tiles| tiles.capacity()).sum();
+        tiles * mem::size_of::<((i64, i64), [u64; 64])>()
+    }
+
+    // threads the last generation actually ran on, small patterns not being worth splitting \\
+    pub fn effective_threads(&self, threads: usize) -> usize {
+        threads.min(self.candidates.len() / MIN_TILES_PER_THREAD).max(1)
+    }
+
+    // one generation, dropping cells outside of a (cols, rows) board if any \\
+    pub fn step(&mut self, size: Option<(i64, i64)>, threads: usize, rule: Rule) {
+        // tiles that may hold live cells next generation: the occupied ones
+        // and their neighbors across any occupied border \\
+        self.candidates.clear();
+        for (&(tx, ty), tile) in &self.current {
+            let (bottom, top) = (tile[0], tile[63]);
+            let left = tile.iter().any(|row| row & 1 != 0);
+            let right = tile.iter().any(|row| row >> 63 != 0);
+            let neighbors = [
+                (0, 0, true),
+                (0, 1, top != 0),
+                (0, -1, bottom != 0),
+                (-1, 0, left),
+                (1, 0, right),
+                (-1, 1, top & 1 != 0),
+                (1, 1, top >> 63 != 0),
+                (-1, -1, bottom & 1 != 0),
+                (1, -1, bottom >> 63 != 0),
+            ];
+            for (dx, dy, occupied) in neighbors {
+                if occupied {
+                    self.candidates.push((tx + dx, ty + dy));
+                }
+            }
+        }
+        self.candidates.sort_unstable_by_key(|&(tx, ty)| (ty, tx));
+        self.candidates.dedup();
+
+        self.next.clear();
+        let threads = self.effective_threads(threads);
+        if threads == 1 {
+            for &(tx, ty) in &self.candidates {
+                let tile = step_tile(&self.current, tx, ty, rule);
+                if tile.iter().any(|row| *row != 0) {
+                    self.next.insert((tx, ty), tile);
+                }
+            }
+        } else {
+            let band = self.candidates.len().div_ceil(threads);
+            if self.bands.len() < threads {
+                self.bands.resize_with(threads, Vec::new);
+            }
+            let current = &self.current;
+            thread::scope(|scope| {
+                for (candidates, tiles) in self.candidates.chunks(band).zip(self.bands.iter_mut()) {
+                    scope.spawn(move || {
+                        for &(tx, ty) in candidates {
+                            let tile = step_tile(current, tx, ty, rule);
+                            if tile.iter().any(|row| *row != 0) {
+                                tiles.push(((tx, ty), tile));
+                            }
+                        }
+                    });
+                }
+            });
+            for tiles in &mut self.bands {
+                self.next.extend(tiles.drain(..));
+            }
+        }
+        if let Some((cols, rows)) = size {
+            clip(&mut self.next, cols, rows);
+        }
+
+        // older <- previous <- current <- next \\
+        mem::swap(&mut self.older, &mut self.previous);
+        mem::swap(&mut self.previous, &mut self.current);
+        mem::swap(&mut self.current, &mut self.next);
+    }
+}
+
+fn clip(tiles: &mut Tiles, cols: i64, rows: i64) {
+    tiles.retain(|&(tx, ty), tile| {
+        let (ox, oy) = (tx * TILE, ty * TILE);
+        let lo = (-ox).clamp(0, TILE);
+        let hi = (cols - ox).clamp(0, TILE);
+        let mask = if hi <= lo {0} else if hi - lo == TILE {u64::MAX} else {((1u64 << (hi - lo)) - 1) << lo};
+        for (by, row) in tile.iter_mut().enumerate() {
+            let y = oy + by as i64;
+            *row &= if y >= 0 && y < rows {mask} else {0};
+        }
+        tile.iter().any(|row| *row != 0)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use super::*;
+    use super::super::rules::CONWAY;
+
+    const HIGHLIFE: Rule = Rule {birth: 1 << 3 | 1 << 6, survival: 1 << 2 | 1 << 3};
+
+    // one generation of the rule over a plain set of cells, then clipped to the board \\
+    fn reference_step(cells: &HashSet<(i64, i64)>, rule: Rule, size: Option<(i64, i64)>) -> HashSet<(i64, i64)> {
+        let mut neighbors: HashMap<(i64, i64), u32> = HashMap::new();
+        for &(x, y) in cells {
+            for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                *neighbors.entry((x + dx, y + dy)).or_insert(0) += 1;
+            }
+        }
+        neighbors.into_iter()
+            .filter(|&(cell, count)| rule.next_state(cells.contains(&cell), count))
+            .map(|(cell, _)| cell)
+            .filter(|&(x, y)| size.is_none_or(|(cols, rows)| x >= 0 && y >= 0 && x < cols && y < rows))
+            .collect()
+    }
+
+    // random cells over a (x, y, cols, rows) rectangle \\
+    fn soup(seed: u64, (x0, y0, cols, rows): (i64, i64, i64, i64)) -> HashSet<(i64, i64)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (x0..x0 + cols).flat_map(|x| (y0..y0 + rows).map(move |y| (x, y))).filter(|_| rng.gen_bool(0.35)).collect()
+    }
+
+    fn grid(cells: &HashSet<(i64, i64)>) -> BitGrid {
+        let mut grid = BitGrid::new();
+        for &(x, y) in cells {
+            grid.set(x, y, true);
+        }
+        grid
+    }
+
+    fn cells(grid: &BitGrid) -> HashSet<(i64, i64)> {
+        match grid.bounding_box() {
+            Some((x0, y0, x1, y1)) => grid.cells_in(0, x0, y0, x1, y1).into_iter().collect(),
+            None => HashSet::new(),
+        }
+    }
+
+    fn matches_the_reference(cells_at_start: HashSet<(i64, i64)>, rule: Rule, size: Option<(i64, i64)>, generations: u32) {
+        let mut expected = cells_at_start;
+        let mut grid = grid(&expected);
+        for generation in 1..=generations {
+            grid.step(size, 1, rule);
+            expected = reference_step(&expected, rule, size);
+            assert!(cells(&grid) == expected, "generation {}", generation);
+        }
+    }
+
+    #[test]
+    fn conway_matches_the_reference_across_tile_edges() {
+        // a soup straddling the corner where four tiles meet \\
+        matches_the_reference(soup(1, (-40, -40, 80, 80)), CONWAY, None, 300);
+    }
+
+    #[test]
+    fn other_rules_match_the_reference_across_tile_edges() {
+        assert!(!HIGHLIFE.is_conway());
+        matches_the_reference(soup(2, (-40, 30, 80, 80)), HIGHLIFE, None, 300);
+    }
+
+    #[test]
+    fn bounded_boards_match_the_reference() {
+        // a board whose edges fall inside tiles, filled from edge to edge \\
+        let board = (100, 70);
+        matches_the_reference(soup(3, (0, 0, board.0, board.1)), CONWAY, Some(board), 300);
+        matches_the_reference(soup(4, (0, 0, board.0, board.1)), HIGHLIFE, Some(board), 300);
+    }
+
+    #[test]
+    fn threads_do_not_change_the_result() {
+        // a soup over 8x8 tiles, enough for every thread to get a band \\
+        let start = soup(5, (0, 0, 512, 512));
+        let (mut single, mut banded) = (grid(&start), grid(&start));
+        for _ in 0..50 {
+            single.step(None, 1, CONWAY);
+            banded.step(None, 4, CONWAY);
+            assert_eq!(banded.effective_threads(4), 4);
+            assert!(banded.same_cells(&single));
+        }
+        for _ in 0..50 {
+            single.step(Some((480, 500)), 1, HIGHLIFE);
+            banded.step(Some((480, 500)), 4, HIGHLIFE);
+        }
+        assert!(banded.effective_threads(4) > 1);
+        assert!(banded.same_cells(&single));
+    }
+}
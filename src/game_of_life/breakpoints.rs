@@ -0,0 +1,90 @@
+use super::{Population, cycles::Detection};
+
+// Conditions that pause a running simulation, checked after every step.
+// A generation target is dropped once reached; the other breakpoints stay
+// until cleared and fire each time their condition turns true, so a
+// population that stays small does not keep pausing the game.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Breakpoint {
+    Generation(u64),
+    PopulationBelow(u64),
+    Birth(i64, i64, i64, i64),
+    Stable,
+}
+
+impl Breakpoint {
+    pub fn describe(&self) -> String {
+        match *self {
+            Breakpoint::Generation(generation) => format!("run until generation {}", generation),
+            Breakpoint::PopulationBelow(count) => format!("pause when population drops below {}", count),
+            Breakpoint::Birth(x0, y0, x1, y1) => format!("pause on births in ({}, {})-({}, {})", x0, y0, x1, y1),
+            Breakpoint::Stable => String::from("pause when the pattern stabilizes"),
+        }
+    }
+
+    // why the step from `before` to `population` hits the breakpoint, if it does \\
+    pub fn check(&self, before: &Population, population: &Population, detection: Option<Detection>) -> Option<String> {
+        match *self {
+            Breakpoint::Generation(generation) if before.generation < generation && population.generation >= generation =>
+                Some(format!("reached generation {}", population.generation)),
+            Breakpoint::PopulationBelow(count) if before.count() >= count && population.count() < count =>
+                Some(format!("population dropped below {}", count)),
+            Breakpoint::Birth(x0, y0, x1, y1) => population.cells_in(x0, y0, x1, y1).into_iter()
+                .find(|&(x, y)| !before.get(x, y))
+                .map(|(x, y)| format!("cell born at ({}, {})", x, y)),
+            Breakpoint::Stable => detection.map(|detection| detection.describe()),
+            _ => None,
+        }
+    }
+
+    // births and population drops are only seen against the generation just
+    // before; a target or a detection works from any earlier one \\
+    pub fn compares_steps(&self) -> bool {
+        matches!(self, Breakpoint::PopulationBelow(_) | Breakpoint::Birth(..))
+    }
+
+    pub fn once(&self) -> bool {
+        matches!(self, Breakpoint::Generation(_))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a blinker, which keeps three cells and gives birth to two every step \\
+    fn blinker() -> Population {
+        let mut population = Population::new();
+        for x in 0..3 {
+            population.set(x, 0, true);
+        }
+        population
+    }
+
+    #[test]
+    fn generation_targets_fire_from_any_earlier_generation() {
+        let before = blinker();
+        let mut population = before.clone();
+        for _ in 0..5 {
+            population.get_next_gen();
+        }
+        assert!(Breakpoint::Generation(3).check(&before, &population, None).is_some());
+        assert!(Breakpoint::Generation(6).check(&before, &population, None).is_none());
+        assert!(!Breakpoint::Generation(3).compares_steps());
+    }
+
+    #[test]
+    fn births_and_drops_compare_with_the_previous_generation() {
+        let before = blinker();
+        let mut population = before.clone();
+        population.get_next_gen();
+        assert_eq!(Breakpoint::Birth(1, 1, 1, 1).check(&before, &population, None), Some(String::from("cell born at (1, 1)")));
+        assert!(Breakpoint::Birth(5, 5, 9, 9).check(&before, &population, None).is_none());
+        assert!(Breakpoint::PopulationBelow(4).check(&before, &population, None).is_none());
+        population.set(1, 1, false);
+        assert!(Breakpoint::PopulationBelow(3).check(&before, &population, None).is_some());
+        assert!(Breakpoint::Birth(0, 0, 0, 0).compares_steps());
+    }
+}
@@ -0,0 +1,287 @@
+use std::{collections::{HashMap, HashSet}, sync::OnceLock};
+use tui::style::Color;
+use super::{Population, formats::{self, Format}};
+
+// Live cells are grouped into 8-connected components, and each component
+// is recognized by comparing its canonical form, the smallest of its eight
+// rotations and reflections moved to the origin, against every phase of a
+// table of common objects. Some objects, like the aircraft carrier or one
+// phase of the beacon, fall apart into pieces two cells away from each
+// other; unrecognized pieces that close are gathered one at a time until
+// together they make a known object.
+
+const MAX_OBJECT_CELLS: usize = 64;
+// furthest apart two pieces of one object may be, along either axis \\
+const MERGE_DISTANCE: i64 = 2;
+
+type Known = HashMap<Vec<(i64, i64)>, (Kind, &'static str)>;
+
+type Transform = fn(i64, i64) -> (i64, i64);
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    StillLife,
+    Oscillator,
+    Spaceship,
+    Unknown,
+}
+
+impl Kind {
+    pub fn color(self) -> Color {
+        match self {
+            Kind::StillLife => Color::Green,
+            Kind::Oscillator => Color::Yellow,
+            Kind::Spaceship => Color::Magenta,
+            Kind::Unknown => Color::Rgb(0, 255, 255),
+        }
+    }
+}
+
+// (kind, name, rle, period) \\
+const OBJECTS: [(Kind, &str, &str, u64); 18] = [
+    (Kind::StillLife, "Block", "2o$2o!", 1),
+    (Kind::StillLife, "Beehive", "b2o$o2bo$b2o!", 1),
+    (Kind::StillLife, "Loaf", "b2o$o2bo$bobo$2bo!", 1),
+    (Kind::StillLife, "Boat", "2o$obo$bo!", 1),
+    (Kind::StillLife, "Ship", "2o$obo$b2o!", 1),
+    (Kind::StillLife, "Tub", "bo$obo$bo!", 1),
+    (Kind::StillLife, "Pond", "b2o$o2bo$o2bo$b2o!", 1),
+    (Kind::StillLife, "Long boat", "2o$obo$bobo$2bo!", 1),
+    (Kind::StillLife, "Barge", "bo$obo$bobo$2bo!", 1),
+    (Kind::StillLife, "Snake", "2obo$ob2o!", 1),
+    (Kind::StillLife, "Aircraft carrier", "2o$o2bo$2b2o!", 1),
+    (Kind::Oscillator, "Blinker", "3o!", 2),
+    (Kind::Oscillator, "Toad", "b3o$3o!", 2),
+    (Kind::Oscillator, "Beacon", "2o$o$3bo$2b2o!", 2),
+    (Kind::Spaceship, "Glider", "bo$2bo$3o!", 4),
+    (Kind::Spaceship, "LWSS", "bo2bo$o4b$o3bo$4o!", 4),
+    (Kind::Spaceship, "MWSS", "3bo$bo3bo$o$o4bo$5o!", 4),
+    (Kind::Spaceship, "HWSS", "3b2o$bo4bo$o$o5bo$6o!", 4),
+];
+
+
+pub struct Object {
+    pub kind: Kind,
+    pub name: Option<&'static str>,
+    pub cells: Vec<(i64, i64)>,
+    pub bounds: (i64, i64, i64, i64),
+}
+
+
+pub struct Census {
+    pub generation: u64,
+    pub objects: Vec<Object>,
+}
+
+impl Census {
+    pub fn take(population: &Population) -> Census {
+        let known = known_objects();
+        let pieces: Vec<Object> = components(population.live_cells()).into_iter().map(|cells| identify(known, cells)).collect();
+        let mut taken = vec![false; pieces.len()];
+        let mut objects = Vec::new();
+        for start in 0..pieces.len() {
+            if pieces[start].name.is_none() && !taken[start] {
+                if let Some((group, object)) = merge_pieces(known, &pieces, &taken, start) {
+                    group.iter().for_each(|&piece| taken[piece] = true);
+                    objects.push(object);
+                }
+            }
+        }
+        objects.extend(pieces.into_iter().zip(taken).filter(|(_, taken)| !taken).map(|(piece, _)| piece));
+        Census {generation: population.generation, objects}
+    }
+
+    // (kind, name, count) of the recognized objects, most common first within a kind \\
+    pub fn counts(&self) -> Vec<(Kind, &'static str, usize)> {
+        let mut counts: HashMap<&'static str, (Kind, usize)> = HashMap::new();
+        for object in &self.objects {
+            if let Some(name) = object.name {
+                counts.entry(name).or_insert((object.kind, 0)).1 += 1;
+            }
+        }
+        let mut counts: Vec<(Kind, &'static str, usize)> = counts.into_iter().map(|(name, (kind, count))| (kind, name, count)).collect();
+        counts.sort_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)).then(a.1.cmp(b.1)));
+        counts
+    }
+
+    pub fn unknown(&self) -> impl Iterator<Item = &Object> {
+        self.objects.iter().filter(|object| object.name.is_none())
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for (_, name, count) in self.counts() {
+            report.push_str(&format!("{} {}\n", name, count));
+        }
+        for object in self.unknown() {
+            let (x0, y0, x1, y1) = object.bounds;
+            report.push_str(&format!("unknown {} cells at ({}, {})-({}, {})\n", object.cells.len(), x0, y0, x1, y1));
+        }
+        report
+    }
+}
+
+
+// canonical form of every phase of the known objects, built on first use \\
+fn known_objects() -> &'static Known {
+    static KNOWN: OnceLock<Known> = OnceLock::new();
+    KNOWN.get_or_init(|| {
+        let mut known = HashMap::new();
+        for (kind, name, rle, period) in OBJECTS {
+            let mut population = formats::read(Format::Rle, rle).expect("built-in objects are valid RLE").unbounded();
+            for _ in 0..period {
+                known.insert(canonical(&population.live_cells()), (kind, name));
+                population.get_next_gen();
+            }
+        }
+        known
+    })
+}
+
+fn identify(known: &Known, cells: Vec<(i64, i64)>) -> Object {
+    let bounds = bounds(&cells);
+    let (kind, name) = if cells.len() <= MAX_OBJECT_CELLS {
+        known.get(&canonical(&cells)).map_or((Kind::Unknown, None), |&(kind, name)| (kind, Some(name)))
+    } else {
+        (Kind::Unknown, None)
+    };
+    Object {kind, name, cells, bounds}
+}
+
+// grows a group of unrecognized pieces from `start`, adding nearby ones one
+// at a time, until together they are recognized \\
+fn merge_pieces(known: &Known, pieces: &[Object], taken: &[bool], start: usize) -> Option<(Vec<usize>, Object)> {
+    let mut group = vec![start];
+    let mut cells = pieces[start].cells.clone();
+    let mut idx = 0;
+    while idx < group.len() {
+        for other in 0..pieces.len() {
+            if pieces[other].name.is_some() || taken[other] || group.contains(&other) || !near(&pieces[group[idx]], &pieces[other]) {
+                continue;
+            }
+            group.push(other);
+            cells.extend(&pieces[other].cells);
+            if cells.len() > MAX_OBJECT_CELLS {
+                return None;
+            }
+            let object = identify(known, cells.clone());
+            if object.name.is_some() {
+                return Some((group, object));
+            }
+        }
+        idx += 1;
+    }
+    None
+}
+
+// whether two objects have cells within MERGE_DISTANCE of each other \\
+fn near(a: &Object, b: &Object) -> bool {
+    let ((ax0, ay0, ax1, ay1), (bx0, by0, bx1, by1)) = (a.bounds, b.bounds);
+    if ax0 > bx1 + MERGE_DISTANCE || bx0 > ax1 + MERGE_DISTANCE || ay0 > by1 + MERGE_DISTANCE || by0 > ay1 + MERGE_DISTANCE {
+        return false;
+    }
+    a.cells.iter().any(|&(x, y)| b.cells.iter().any(|&(u, v)| (x - u).abs() <= MERGE_DISTANCE && (y - v).abs() <= MERGE_DISTANCE))
+}
+
+fn components(cells: Vec<(i64, i64)>) -> Vec<Vec<(i64, i64)>> {
+    let mut remaining: HashSet<(i64, i64)> = cells.into_iter().collect();
+    let mut components = Vec::new();
+    while let Some(&start) = remaining.iter().next() {
+        remaining.remove(&start);
+        let mut component = vec![start];
+        let mut idx = 0;
+        while idx < component.len() {
+            let (x, y) = component[idx];
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    if remaining.remove(&(x + dx, y + dy)) {
+                        component.push((x + dx, y + dy));
+                    }
+                }
+            }
+            idx += 1;
+        }
+        components.push(component);
+    }
+    components
+}
+
+fn bounds(cells: &[(i64, i64)]) -> (i64, i64, i64, i64) {
+    cells.iter().fold((i64::MAX, i64::MAX, i64::MIN, i64::MIN), |(x0, y0, x1, y1), &(x, y)| {
+        (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+    })
+}
+
+// smallest of the eight symmetric images, moved to the origin and sorted \\
+fn canonical(cells: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let transforms: [Transform; 8] = [
+        |x, y| (x, y),
+        |x, y| (-y, x),
+        |x, y| (-x, -y),
+        |x, y| (y, -x),
+        |x, y| (-x, y),
+        |x, y| (x, -y),
+        |x, y| (y, x),
+        |x, y| (-y, -x),
+    ];
+    transforms.iter()
+        .map(|transform| {
+            let mut image: Vec<(i64, i64)> = cells.iter().map(|&(x, y)| transform(x, y)).collect();
+            let (x0, y0, _, _) = bounds(&image);
+            for cell in image.iter_mut() {
+                *cell = (cell.0 - x0, cell.1 - y0);
+            }
+            image.sort_unstable();
+            image
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn census(cells: &[(i64, i64)]) -> Census {
+        let mut population = Population::new();
+        for &(x, y) in cells {
+            population.set(x, y, true);
+        }
+        Census::take(&population)
+    }
+
+    fn names(census: &Census) -> Vec<Option<&'static str>> {
+        let mut names: Vec<Option<&'static str>> = census.objects.iter().map(|object| object.name).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn recognizes_every_phase_of_every_object() {
+        for (kind, name, rle, period) in OBJECTS {
+            let mut population = formats::read(Format::Rle, rle).unwrap().unbounded();
+            for phase in 0..period {
+                let census = Census::take(&population);
+                assert_eq!(names(&census), vec![Some(name)], "{} phase {}", name, phase);
+                assert!(census.objects[0].kind == kind);
+                population.get_next_gen();
+            }
+        }
+    }
+
+    #[test]
+    fn objects_close_together_stay_apart() {
+        // two blocks two cells apart, and a blinker next to a boat \\
+        let census_blocks = census(&[(0, 0), (1, 0), (0, 1), (1, 1), (4, 0), (5, 0), (4, 1), (5, 1)]);
+        assert_eq!(names(&census_blocks), vec![Some("Block"), Some("Block")]);
+        let mixed = census(&[(0, 0), (1, 0), (2, 0), (5, 0), (6, 0), (5, 1), (7, 1), (6, 2)]);
+        assert_eq!(names(&mixed), vec![Some("Blinker"), Some("Boat")]);
+    }
+
+    #[test]
+    fn leaves_unrecognized_pieces_alone() {
+        let census = census(&[(0, 0), (1, 0), (0, 1), (10, 10)]);
+        assert_eq!(names(&census), vec![None, None]);
+        assert_eq!(census.unknown().count(), 2);
+    }
+}
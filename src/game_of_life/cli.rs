@@ -0,0 +1,286 @@
+use std::{io, path::Path, time::Instant};
+use super::{formats, images, run_life, max_threads, parse_board_size, describe_board, Population, stats::Stats, cycles::CycleDetector, census::Census, soups::{Soup, Symmetry}, rules::Rule, theme::{Theme, THEMES}};
+
+const USAGE: &str = "usage:
+    game_collection life new [--size <cols>x<rows>]
+    game_collection life open <pattern> [--size <cols>x<rows>]
+    game_collection life convert <input> <output>
+    game_collection life run --pattern <pattern> [--gens <generations>] [--rule <B3/S23>] [--out <pattern>]
+    game_collection life stats <pattern> [--gens <generations>]
+    game_collection life detect <pattern> [--gens <generations>]
+    game_collection life census <pattern> [--gens <generations>]
+    game_collection life soup [--size <cells>] [--density <0-1>] [--symmetry <none|C2|C4|D4|D8>] [--seed <seed>] [--out <pattern>]
+    game_collection life image <pattern> --out <image> [--gens <generations>] [--cell <pixels>] [--theme <teal|ember>]
+    game_collection life gif <pattern> --out <gif> [--from <generation>] [--gens <generations>] [--cell <pixels>] [--delay <ms>] [--theme <teal|ember>]
+    game_collection life bench [--size <cells>] [--gens <generations>] [--threads <count>]
+
+pattern formats are chosen by extension: .rle, .cells, .lif, .mc
+image formats are chosen by extension: .pbm, .png";
+
+
+fn usage_error() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, USAGE)
+}
+
+// value following `--name`, parsed \\
+fn flag<T: std::str::FromStr>(args: &[String], name: &str) -> io::Result<Option<T>> {
+    match args.iter().position(|arg| arg == name) {
+        None => Ok(None),
+        Some(idx) => args.get(idx + 1)
+            .and_then(|value| value.parse().ok())
+            .map(Some)
+            .ok_or_else(usage_error),
+    }
+}
+
+// color scheme named by `--theme`, the first one otherwise \\
+fn theme(args: &[String]) -> io::Result<&'static Theme> {
+    match flag::<String>(args, "--theme")? {
+        None => Ok(&THEMES[0]),
+        Some(name) => THEMES.iter().find(|theme| theme.name == name).ok_or_else(usage_error),
+    }
+}
+
+// board of `--size` cells, the unbounded plane otherwise \\
+fn board_size(args: &[String]) -> io::Result<Option<(i64, i64)>> {
+    flag::<String>(args, "--size")?
+        .map(|size| parse_board_size(&size).ok_or_else(usage_error))
+        .transpose()
+}
+
+// entry point for `game_collection life ...` \\
+pub fn run_cli(args: &[String]) -> io::Result<()> {
+    match args.first().map(String::as_str) {
+        Some("new") => {
+            let population = Population::new().resized(board_size(args)?).0;
+            crate::with_terminal(|terminal| run_life(terminal, population))
+        }
+        Some("open") => match args.get(1) {
+            Some(pattern) if !pattern.starts_with("--") => {
+                let population = formats::load(Path::new(pattern))?;
+                let population = match board_size(args)? {
+                    Some(size) => match population.resized(Some(size)) {
+                        (board, 0) => board,
+                        (_, clipped) => return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("{} cells of {} do not fit on a {} board", clipped, pattern, describe_board(Some(size))),
+                        )),
+                    },
+                    None => population.unbounded(),
+                };
+                crate::with_terminal(|terminal| run_life(terminal, population))
+            }
+            _ => Err(usage_error()),
+        },
+        Some("convert") => match &args[1..] {
+            [input, output] => convert(Path::new(input), Path::new(output)),
+            _ => Err(usage_error()),
+        },
+        Some("run") => {
+            let pattern: String = flag(args, "--pattern")?.ok_or_else(usage_error)?;
+            let output: Option<String> = flag(args, "--out")?;
+            run(
+                Path::new(&pattern),
+                flag(args, "--gens")?.unwrap_or(1000),
+                flag::<String>(args, "--rule")?.map(|rule| rule.parse()).transpose()?,
+                output.as_deref().map(Path::new),
+            )
+        }
+        Some("stats") => match args.get(1) {
+            Some(pattern) if !pattern.starts_with("--") => stats(Path::new(pattern), flag(args, "--gens")?.unwrap_or(100)),
+            _ => Err(usage_error()),
+        },
+        Some("detect") => match args.get(1) {
+            Some(pattern) if !pattern.starts_with("--") => detect(Path::new(pattern), flag(args, "--gens")?.unwrap_or(10000)),
+            _ => Err(usage_error()),
+        },
+        Some("census") => match args.get(1) {
+            Some(pattern) if !pattern.starts_with("--") => census(Path::new(pattern), flag(args, "--gens")?.unwrap_or(0)),
+            _ => Err(usage_error()),
+        },
+        Some("soup") => {
+            let mut soup = Soup::new();
+            soup.reseed();
+            soup.density = flag(args, "--density")?.unwrap_or(soup.density);
+            soup.seed = flag(args, "--seed")?.unwrap_or(soup.seed);
+            if let Some(name) = flag::<String>(args, "--symmetry")? {
+                soup.symmetry = Symmetry::from_name(&name).ok_or_else(usage_error)?;
+            }
+            let output: Option<String> = flag(args, "--out")?;
+            generate_soup(&soup, flag(args, "--size")?.unwrap_or(16), output.as_deref().map(Path::new))
+        }
+        Some("image") => match args.get(1) {
+            Some(pattern) if !pattern.starts_with("--") => {
+                let output: String = flag(args, "--out")?.ok_or_else(usage_error)?;
+                image(
+                    Path::new(pattern),
+                    Path::new(&output),
+                    flag(args, "--gens")?.unwrap_or(0),
+                    flag(args, "--cell")?.unwrap_or(8),
+                    theme(args)?,
+                )
+            }
+            _ => Err(usage_error()),
+        },
+        Some("gif") => match args.get(1) {
+            Some(pattern) if !pattern.starts_with("--") => {
+                let output: String = flag(args, "--out")?.ok_or_else(usage_error)?;
+                animation(
+                    Path::new(pattern),
+                    Path::new(&output),
+                    flag(args, "--from")?.unwrap_or(0),
+                    flag(args, "--gens")?.unwrap_or(100),
+                    flag(args, "--cell")?.unwrap_or(8),
+                    flag::<u16>(args, "--delay")?.unwrap_or(100) / 10,
+                    theme(args)?,
+                )
+            }
+            _ => Err(usage_error()),
+        },
+        Some("bench") => bench(
+            flag(args, "--size")?.unwrap_or(1024),
+            flag(args, "--gens")?.unwrap_or(200),
+            flag(args, "--threads")?.unwrap_or_else(max_threads),
+        ),
+        _ => Err(usage_error()),
+    }
+}
+
+fn convert(input: &Path, output: &Path) -> io::Result<()> {
+    let population = formats::load(input)?;
+    formats::save(output, &population)
+}
+
+// runs a pattern with no terminal and reports the outcome as JSON \\
+fn run(pattern: &Path, gens: u64, rule: Option<Rule>, output: Option<&Path>) -> io::Result<()> {
+    let mut population = formats::load(pattern)?.unbounded();
+    if let Some(rule) = rule {
+        population.rule = rule;
+    }
+    let mut detector = CycleDetector::new();
+    detector.observe(&population);
+    for _ in 0..gens {
+        population.get_next_gen();
+        detector.observe(&population);
+    }
+    if let Some(path) = output {
+        formats::save(path, &population)?;
+    }
+
+    let bounding_box = match population.bounding_box() {
+        Some((x0, y0, x1, y1)) => format!("[{}, {}, {}, {}]", x0, y0, x1, y1),
+        None => String::from("null"),
+    };
+    let detection = detector.detection().map_or(String::from("null"), |detection| detection.to_json());
+    println!("{{");
+    println!("  \"rule\": \"{}\",", population.rule);
+    println!("  \"generation\": {},", population.generation);
+    println!("  \"population\": {},", population.count());
+    println!("  \"bounding_box\": {},", bounding_box);
+    println!("  \"detection\": {}", detection);
+    println!("}}");
+    Ok(())
+}
+
+// population, births and deaths of every generation as CSV on stdout \\
+fn stats(pattern: &Path, gens: u64) -> io::Result<()> {
+    let mut population = formats::load(pattern)?.unbounded();
+    let mut stats = Stats::new(&population);
+    for _ in 0..gens {
+        let before = population.clone();
+        population.get_next_gen();
+        stats.record(&before, &population);
+    }
+    print!("{}", stats.to_csv());
+    Ok(())
+}
+
+// runs a pattern until it dies out, cycles or stabilizes \\
+fn detect(pattern: &Path, gens: u64) -> io::Result<()> {
+    let mut population = formats::load(pattern)?.unbounded();
+    let mut detector = CycleDetector::new();
+    let mut detection = detector.observe(&population);
+    while detection.is_none() && population.generation < gens {
+        population.get_next_gen();
+        detection = detector.observe(&population);
+    }
+    match detection {
+        Some(detection) => println!("{}", detection.describe()),
+        None => println!("no cycle within {} generations", gens),
+    }
+    Ok(())
+}
+
+// objects found after running a pattern for some generations \\
+fn census(pattern: &Path, gens: u64) -> io::Result<()> {
+    let mut population = formats::load(pattern)?.unbounded();
+    for _ in 0..gens {
+        population.get_next_gen();
+    }
+    print!("{}", Census::take(&population).report());
+    Ok(())
+}
+
+// square soup saved to a pattern file, or printed as RLE \\
+fn generate_soup(soup: &Soup, size: i64, output: Option<&Path>) -> io::Result<()> {
+    if !(0.0..=1.0).contains(&soup.density) || size < 1 {
+        return Err(usage_error());
+    }
+    let mut population = Population::with_size(size, size);
+    soup.fill(&mut population, 0, 0, size - 1, size - 1);
+    match output {
+        Some(path) => {
+            formats::save(path, &population)?;
+            println!("{}", soup.describe());
+        }
+        None => print!("#C {}\n{}", soup.describe(), formats::write(formats::Format::Rle, &population)),
+    }
+    Ok(())
+}
+
+// a pattern, possibly after some generations, as a picture \\
+fn image(pattern: &Path, output: &Path, gens: u64, cell: usize, theme: &Theme) -> io::Result<()> {
+    let mut population = formats::load(pattern)?.unbounded();
+    for _ in 0..gens {
+        population.get_next_gen();
+    }
+    images::save_image(output, &population, None, cell, theme)
+}
+
+// a range of generations as an animated GIF \\
+fn animation(pattern: &Path, output: &Path, from: u64, gens: u64, cell: usize, delay: u16, theme: &Theme) -> io::Result<()> {
+    let mut population = formats::load(pattern)?.unbounded();
+    for _ in 0..from {
+        population.get_next_gen();
+    }
+    images::save_animation(output, &population, None, gens, cell, delay, theme)
+}
+
+// serial against parallel throughput of the packed engine on a random soup \\
+fn bench(size: i64, gens: u64, threads: usize) -> io::Result<()> {
+    let mut soup = Population::new();
+    let mut settings = Soup::new();
+    settings.reseed();
+    settings.fill(&mut soup, 0, 0, size - 1, size - 1);
+    println!("{}x{} soup, {} generations", size, size, gens);
+
+    let mut results: Vec<Population> = Vec::new();
+    for threads in [1, threads.max(1)] {
+        let mut population = soup.clone();
+        population.threads = threads;
+        let start = Instant::now();
+        for _ in 0..gens {
+            population.get_next_gen();
+        }
+        let seconds = start.elapsed().as_secs_f64();
+        println!(
+            "{:>3} thread(s): {:>10.1} gen/s {:>10.1} Mcell/s",
+            threads,
+            gens as f64 / seconds,
+            (size * size) as f64 * gens as f64 / seconds / 1e6,
+        );
+        results.push(population);
+    }
+    println!("identical results: {}", results[0].same_cells(&results[1]));
+    Ok(())
+}
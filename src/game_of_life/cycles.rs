@@ -0,0 +1,250 @@
+use std::{collections::{HashMap, VecDeque}, collections::hash_map::DefaultHasher, hash::{Hash, Hasher}};
+use super::{Population, bitgrid::TILE};
+
+// Consecutive generations are hashed twice: once as they are, which finds
+// still lifes and oscillators, and once moved to the origin, which finds
+// spaceships. Both hashes are sums over the 64 cell rows of the engine's
+// tiles, so no generation is ever listed cell by cell. A methuselah
+// usually settles while throwing gliders away, so neither ever repeats;
+// it is called stable once its population has been periodic for a long
+// enough stretch.
+
+const WINDOW: usize = 1024;
+const MAX_POPULATION_PERIOD: usize = 60;
+const STABLE_SPAN: usize = 200;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Extinct,
+    Still,
+    Oscillator {period: u64},
+    Spaceship {period: u64, dx: i64, dy: i64},
+    Stable {period: u64},
+}
+
+
+#[derive(Clone, Copy)]
+pub struct Detection {
+    pub outcome: Outcome,
+    pub since: u64,
+}
+
+impl Detection {
+    pub fn describe(&self) -> String {
+        match self.outcome {
+            Outcome::Extinct => format!("died out at generation {}", self.since),
+            Outcome::Still => format!("still life since generation {}", self.since),
+            Outcome::Oscillator {period} => format!("period {} oscillator since generation {}", period, self.since),
+            Outcome::Spaceship {period, dx, dy} => format!("period {} spaceship moving ({}, {}) since generation {}", period, dx, dy, self.since),
+            Outcome::Stable {period} => format!("population stable with period {} since generation {}", period, self.since),
+        }
+    }
+
+    pub fn to_json(self) -> String {
+        let (outcome, period, (dx, dy)) = match self.outcome {
+            Outcome::Extinct => ("extinct", 0, (0, 0)),
+            Outcome::Still => ("still", 1, (0, 0)),
+            Outcome::Oscillator {period} => ("oscillator", period, (0, 0)),
+            Outcome::Spaceship {period, dx, dy} => ("spaceship", period, (dx, dy)),
+            Outcome::Stable {period} => ("stable", period, (0, 0)),
+        };
+        format!(
+            "{{\"outcome\": \"{}\", \"period\": {}, \"since\": {}, \"dx\": {}, \"dy\": {}}}",
+            outcome, period, self.since, dx, dy,
+        )
+    }
+}
+
+
+struct Observation {
+    generation: u64,
+    exact: u64,
+    shape: u64,
+}
+
+
+pub struct CycleDetector {
+    recent: VecDeque<Observation>,
+    exact: HashMap<u64, u64>,
+    shapes: HashMap<u64, (u64, i64, i64)>,
+    populations: VecDeque<u64>,
+    detection: Option<Detection>,
+    last_generation: Option<u64>,
+}
+
+impl CycleDetector {
+    pub fn new() -> CycleDetector {
+        CycleDetector {
+            recent: VecDeque::new(),
+            exact: HashMap::new(),
+            shapes: HashMap::new(),
+            populations: VecDeque::new(),
+            detection: None,
+            last_generation: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = CycleDetector::new();
+    }
+
+    pub fn detection(&self) -> Option<Detection> {
+        self.detection
+    }
+
+    // looks at the next generation, returns a detection the first time one is made \\
+    pub fn observe(&mut self, population: &Population) -> Option<Detection> {
+        if self.last_generation.is_some_and(|last| last + 1 != population.generation) {
+            self.reset();
+        }
+        self.last_generation = Some(population.generation);
+        if self.detection.is_some() {
+            return None;
+        }
+
+        let (x0, y0, _, _) = population.bounding_box().unwrap_or((0, 0, 0, 0));
+        let (exact, shape) = fingerprints(population, x0, y0);
+        let count = population.count();
+        let generation = population.generation;
+
+        let detection = if count == 0 {
+            Some(Detection {outcome: Outcome::Extinct, since: generation})
+        } else if let Some(&start) = self.exact.get(&exact) {
+            let period = generation - start;
+            let outcome = if period == 1 {Outcome::Still} else {Outcome::Oscillator {period}};
+            Some(Detection {outcome, since: start})
+        } else if let Some(&(start, sx, sy)) = self.shapes.get(&shape) {
+            Some(Detection {outcome: Outcome::Spaceship {period: generation - start, dx: x0 - sx, dy: y0 - sy}, since: start})
+        } else {
+            self.populations.push_back(count);
+            if self.populations.len() > WINDOW {
+                self.populations.pop_front();
+            }
+            self.stable_population(generation)
+        };
+
+        // first sighting of each state is kept, so the period is the shortest one \\
+        self.exact.entry(exact).or_insert(generation);
+        self.shapes.entry(shape).or_insert((generation, x0, y0));
+        self.recent.push_back(Observation {generation, exact, shape});
+        if self.recent.len() > WINDOW {
+            let old = self.recent.pop_front().unwrap();
+            if self.exact.get(&old.exact) == Some(&old.generation) {
+                self.exact.remove(&old.exact);
+            }
+            if self.shapes.get(&old.shape).is_some_and(|s| s.0 == old.generation) {
+                self.shapes.remove(&old.shape);
+            }
+        }
+
+        self.detection = detection;
+        detection
+    }
+
+    // shortest period the population has kept over the last STABLE_SPAN generations \\
+    fn stable_population(&self, generation: u64) -> Option<Detection> {
+        let populations = &self.populations;
+        let len = populations.len();
+        for period in 1..=MAX_POPULATION_PERIOD {
+            let run = (period..len).rev()
+                .take_while(|&i| populations[i] == populations[i - period])
+                .count();
+            if run >= STABLE_SPAN {
+                let since = generation - (run + period - 1) as u64;
+                return Some(Detection {outcome: Outcome::Stable {period: period as u64}, since});
+            }
+        }
+        None
+    }
+}
+
+fn hash<T: Hash>(value: T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// hashes of the live cells as they are and moved so that (x0, y0) is the
+// origin. Rows are summed, so the order tiles come in does not matter; the
+// moved rows straddle two words and are put back together first. \\
+fn fingerprints(population: &Population, x0: i64, y0: i64) -> (u64, u64) {
+    let mut exact = 0u64;
+    let mut moved: HashMap<(i64, i64), u64> = HashMap::new();
+    population.for_each_tile(|(tx, ty), rows| {
+        let offset = tx * TILE - x0;
+        let (word, shift) = (offset.div_euclid(TILE), offset.rem_euclid(TILE) as u32);
+        for (r, &row) in rows.iter().enumerate().filter(|(_, row)| **row != 0) {
+            let y = ty * TILE + r as i64;
+            exact = exact.wrapping_add(hash((tx, y, row)));
+            let parts = [(word, row << shift), (word + 1, if shift == 0 {0} else {row >> (64 - shift)})];
+            for (word, bits) in parts.into_iter().filter(|(_, bits)| *bits != 0) {
+                *moved.entry((word, y - y0)).or_default() |= bits;
+            }
+        }
+    });
+    let shape = moved.into_iter().fold(0u64, |sum, entry| sum.wrapping_add(hash(entry)));
+    (exact, shape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::hashlife::HashLife;
+
+    const BLOCK: [(i64, i64); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+    const BLINKER: [(i64, i64); 3] = [(0, 0), (1, 0), (2, 0)];
+    // moves one cell right and one down every four generations \\
+    const GLIDER: [(i64, i64); 5] = [(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)];
+
+    // runs a pattern placed at (x, y) on either engine until something is detected \\
+    fn detect(cells: &[(i64, i64)], x: i64, y: i64, hashlife: bool) -> Outcome {
+        let mut population = if hashlife {Population::from_hashlife(HashLife::new())} else {Population::new()};
+        for &(cx, cy) in cells {
+            population.set(x + cx, y + cy, true);
+        }
+        let mut detector = CycleDetector::new();
+        for _ in 0..100 {
+            if let Some(detection) = detector.observe(&population) {
+                return detection.outcome;
+            }
+            population.get_next_gen();
+        }
+        panic!("nothing detected");
+    }
+
+    #[test]
+    fn finds_still_lifes_oscillators_and_spaceships_on_both_engines() {
+        // across tile borders, and on either side of the origin \\
+        for (x, y) in [(0, 0), (62, 62), (-65, 126), (-1, -1)] {
+            for hashlife in [false, true] {
+                assert!(detect(&BLOCK, x, y, hashlife) == Outcome::Still);
+                assert!(detect(&BLINKER, x, y, hashlife) == Outcome::Oscillator {period: 2});
+                assert!(detect(&GLIDER, x, y, hashlife) == Outcome::Spaceship {period: 4, dx: 1, dy: -1});
+            }
+        }
+    }
+
+    #[test]
+    fn finds_extinction() {
+        assert!(detect(&[(0, 0)], 63, 0, false) == Outcome::Extinct);
+        assert!(detect(&[(0, 0)], 63, 0, true) == Outcome::Extinct);
+    }
+
+    #[test]
+    fn moved_patterns_share_a_shape() {
+        let at = |x: i64, y: i64| {
+            let mut population = Population::new();
+            for &(cx, cy) in &GLIDER {
+                population.set(x + cx, y + cy, true);
+            }
+            fingerprints(&population, x, y)
+        };
+        let (exact, shape) = at(0, 0);
+        for (x, y) in [(1, 0), (63, 5), (-200, 17)] {
+            let (moved_exact, moved_shape) = at(x, y);
+            assert_eq!(moved_shape, shape);
+            assert_ne!(moved_exact, exact);
+        }
+    }
+}
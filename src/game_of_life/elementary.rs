@@ -0,0 +1,253 @@
+use std::{io, collections::VecDeque, path::Path};
+use rand::Rng;
+use tui::{
+    backend::Backend,
+    widgets::canvas::Context,
+    text::Span,
+    Terminal
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use super::{Population, paint_population, images, view::Viewport, theme::{Theme, THEMES}, prompt::{Prompt, Ask}, mode::{Mode, Controls, run_mode, arrow}};
+
+// Elementary cellular automata: a row of cells where each cell's next state
+// depends on itself and its two neighbors. The eight neighborhoods, read as
+// three bit numbers, pick a bit of the rule number (Wolfram's numbering).
+// The row wraps around, and generations go down the screen: generation t is
+// drawn at y = -t, so exports come out as the familiar triangles.
+
+const HELP: &str = "[p]: 'pause/resume', [n]: 'step', [[/]]: 'previous/next rule', [r]: 'rule number', [s]: 'single seed/random row', [c]: 'restart', [arrows]: 'pan', [i/o]: 'zoom in/out', [0]: 'follow', [+/-]: 'faster/slower', [m]: 'renderer', [T]: 'theme', [G]: 'export image', [q]: 'quit', [?]: 'all keys'";
+const USAGE: &str = "usage: game_collection elementary [rule] [--random]";
+const DEFAULT_RULE: u8 = 30;
+const START_SPEED: usize = 6;
+// generations kept on screen and in exports \\
+const MAX_ROWS: usize = 4096;
+const IMAGE_FILE: &str = "elementary.png";
+const EXPORT_CELL: usize = 4;
+
+
+struct Automaton {
+    rule: u8,
+    random: bool,
+    width: usize,
+    rows: VecDeque<Vec<bool>>,
+    generation: u64,
+}
+
+impl Automaton {
+    fn new(rule: u8, random: bool, width: usize) -> Automaton {
+        let width = width.max(3);
+        let row = if random {
+            let mut rng = rand::thread_rng();
+            (0..width).map(|_| rng.gen()).collect()
+        } else {
+            let mut row = vec![false; width];
+            row[width / 2] = true;
+            row
+        };
+        Automaton {rule, random, width, rows: VecDeque::from([row]), generation: 0}
+    }
+
+    fn step(&mut self) {
+        let last = self.rows.back().unwrap();
+        let n = self.width;
+        let row = (0..n)
+            .map(|x| {
+                let neighborhood = (last[(x + n - 1) % n] as u8) << 2 | (last[x] as u8) << 1 | last[(x + 1) % n] as u8;
+                self.rule >> neighborhood & 1 == 1
+            })
+            .collect();
+        self.rows.push_back(row);
+        if self.rows.len() > MAX_ROWS {
+            self.rows.pop_front();
+        }
+        self.generation += 1;
+    }
+
+    // live cells of the kept generations between two heights, the newest at y = -generation \\
+    fn cells_in(&self, y0: i64, y1: i64) -> Vec<(i64, i64)> {
+        let first = self.generation + 1 - self.rows.len() as u64;
+        self.rows.iter().enumerate()
+            .map(|(t, row)| (-((first + t as u64) as i64), row))
+            .filter(|&(y, _)| y >= y0 && y <= y1)
+            .flat_map(|(y, row)| row.iter().enumerate().filter(|(_, &alive)| alive).map(move |(x, _)| (x as i64, y)))
+            .collect()
+    }
+
+    fn population(&self) -> Population {
+        let mut population = Population::new();
+        for (x, y) in self.cells_in(i64::MIN, 0) {
+            population.set(x, y, true);
+        }
+        population
+    }
+
+    // the kept generations as a picture, rows of the ring included where empty \\
+    fn export(&self, path: &Path, theme: &Theme) -> io::Result<()> {
+        let first = self.generation + 1 - self.rows.len() as u64;
+        let area = (0, -(self.generation as i64), self.width as i64 - 1, -(first as i64));
+        images::save_image(path, &self.population(), Some(area), EXPORT_CELL, theme)
+    }
+}
+
+
+// keeps the newest generation at the bottom, filling the view from the top at first \\
+fn follow(view: &mut Viewport, automaton: &Automaton) {
+    let (w, h) = view.span();
+    view.x = (automaton.width as i64 - w) / 2;
+    view.y = -(automaton.generation as i64).max(h - 1);
+}
+
+pub fn run_elementary<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+    run_automaton(terminal, DEFAULT_RULE, false)
+}
+
+pub fn run_elementary_cli(args: &[String]) -> io::Result<()> {
+    let random = args.iter().any(|arg| arg == "--random");
+    let rules: Vec<&String> = args.iter().filter(|arg| *arg != "--random").collect();
+    let rule = match rules[..] {
+        [] => DEFAULT_RULE,
+        [rule] => rule.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, USAGE))?,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, USAGE)),
+    };
+    crate::with_terminal(|terminal| run_automaton(terminal, rule, random))
+}
+
+fn run_automaton<B: Backend>(terminal: &mut Terminal<B>, rule: u8, random: bool) -> io::Result<()> {
+    let mut controls = Controls::new(terminal.size()?, None);
+    (controls.paused, controls.speed) = (false, START_SPEED);
+    let automaton = Automaton::new(rule, random, controls.view.span().0 as usize);
+    run_mode(terminal, &mut Elementary {automaton, theme: 0, following: true}, controls)
+}
+
+struct Elementary {
+    automaton: Automaton,
+    theme: usize,
+    following: bool,
+}
+
+impl Mode for Elementary {
+    fn name(&self) -> String {
+        String::from("Elementary CA")
+    }
+
+    fn help(&self) -> &'static str {
+        HELP
+    }
+
+    fn stats(&self) -> Vec<Span<'static>> {
+        let automaton = &self.automaton;
+        vec![Span::raw(format!(
+            "Rule: {} | Generation: {} | Width: {} | Start: {}",
+            automaton.rule, automaton.generation, automaton.width,
+            if automaton.random {"random"} else {"single seed"},
+        ))]
+    }
+
+    fn paint(&self, ctx: &mut Context, controls: &Controls) {
+        let view = &controls.view;
+        let (_, h) = view.span();
+        let visible: Vec<(i64, i64)> = self.automaton.cells_in(view.y, view.y + h - 1).into_iter().filter(|&(x, y)| view.contains(x, y)).collect();
+        paint_population(ctx, view, &visible, THEMES[self.theme].live);
+    }
+
+    fn step(&mut self) {
+        self.automaton.step();
+    }
+
+    fn frame(&mut self, view: &mut Viewport) {
+        if self.following {
+            follow(view, &self.automaton);
+        }
+    }
+
+    fn answer(&mut self, prompt: &Prompt) -> Result<(), String> {
+        self.automaton.rule = prompt.text.parse().map_err(|_| format!("'{}' is not a rule between 0 and 255", prompt.text))?;
+        Ok(())
+    }
+
+    fn key(&mut self, key: KeyEvent, controls: &mut Controls) -> io::Result<bool> {
+        let automaton = &mut self.automaton;
+        let width = controls.view.span().0 as usize;
+        match key.code {
+            KeyCode::Char('[') => automaton.rule = automaton.rule.wrapping_sub(1),
+            KeyCode::Char(']') => automaton.rule = automaton.rule.wrapping_add(1),
+            KeyCode::Char('r') => controls.prompt = Some(Prompt::new(Ask::RuleNumber)),
+            KeyCode::Char('s') => {
+                *automaton = Automaton::new(automaton.rule, !automaton.random, width);
+                self.following = true;
+            }
+            KeyCode::Char('c') => {
+                *automaton = Automaton::new(automaton.rule, automaton.random, width);
+                self.following = true;
+            }
+            KeyCode::Char('0') => self.following = true,
+            KeyCode::Char('m') => {
+                controls.view.renderer = controls.view.renderer.next();
+                controls.view.zoom(0, 0, 0);
+                controls.status = Some(format!("renderer: {}", controls.view.renderer.name()));
+            }
+            KeyCode::Char('T') => {
+                self.theme = (self.theme + 1) % THEMES.len();
+                controls.status = Some(format!("theme: {}", THEMES[self.theme].name));
+            }
+            KeyCode::Char('G') => {
+                controls.status = Some(match automaton.export(Path::new(IMAGE_FILE), &THEMES[self.theme]) {
+                    Ok(()) => format!("image saved to {}", IMAGE_FILE),
+                    Err(err) => format!("export failed: {}", err),
+                });
+            }
+            // panning stops following, then pans as usual \\
+            code => {
+                if arrow(code).is_some() {
+                    self.following = false;
+                }
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(automaton: &Automaton, generation: i64) -> Vec<i64> {
+        let mut xs: Vec<i64> = automaton.cells_in(-generation, -generation).into_iter().map(|(x, _)| x).collect();
+        xs.sort();
+        xs
+    }
+
+    #[test]
+    fn rule_90_draws_pascals_triangle_mod_2() {
+        let mut automaton = Automaton::new(90, false, 11);
+        for _ in 0..3 {
+            automaton.step();
+        }
+        assert_eq!(row(&automaton, 0), vec![5]);
+        assert_eq!(row(&automaton, 1), vec![4, 6]);
+        assert_eq!(row(&automaton, 2), vec![3, 7]);
+        assert_eq!(row(&automaton, 3), vec![2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn rows_wrap_around() {
+        let mut automaton = Automaton::new(90, false, 3);
+        automaton.step();
+        automaton.step();
+        assert_eq!(row(&automaton, 1), vec![0, 2]);
+        assert_eq!(row(&automaton, 2), vec![0, 2]);
+    }
+
+    #[test]
+    fn keeps_the_newest_generations() {
+        let mut automaton = Automaton::new(1, false, 5);
+        for _ in 0..MAX_ROWS + 10 {
+            automaton.step();
+        }
+        assert_eq!(automaton.rows.len(), MAX_ROWS);
+        assert!(automaton.cells_in(-10, 0).is_empty());
+        let (_, y0, _, y1) = automaton.population().bounding_box().unwrap();
+        assert_eq!((y0, y1), (-(MAX_ROWS as i64 + 10), -11));
+    }
+}
@@ -0,0 +1,488 @@
+use std::{collections::HashMap, fs, io, path::Path};
+use super::{Population, Engine, rules::Rule, hashlife::{HashLife, NodeId, ALIVE, DEAD, MAX_LEVEL}};
+
+// Pattern files list rows from top to bottom while the population grid
+// grows upward (row 0 is drawn at the bottom of the canvas), so every
+// reader and writer flips the y axis.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Plaintext,
+    Life106,
+    Rle,
+    Macrocell,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> io::Result<Format> {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("cells") | Some("txt") => Ok(Format::Plaintext),
+            Some("lif") | Some("life") => Ok(Format::Life106),
+            Some("rle") => Ok(Format::Rle),
+            Some("mc") => Ok(Format::Macrocell),
+            _ => Err(invalid(format!("unknown pattern format: {}", path.display()))),
+        }
+    }
+}
+
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// builds a population just big enough to hold the cells, given top-down rows \\
+fn from_rows(cells: &[(usize, usize)]) -> Population {
+    let cols = cells.iter().map(|(x, _)| x + 1).max().unwrap_or(1);
+    let rows = cells.iter().map(|(_, y)| y + 1).max().unwrap_or(1);
+    let mut population = Population::with_size(cols as i64, rows as i64);
+    for &(x, y) in cells {
+        population.set(x as i64, (rows - 1 - y) as i64, true);
+    }
+    population
+}
+
+// live cells relative to the bounding box, given top-down rows \\
+fn to_rows(population: &Population) -> (i64, i64, Vec<Vec<bool>>) {
+    match population.bounding_box() {
+        None => (0, 0, Vec::new()),
+        Some((x0, y0, x1, y1)) => {
+            let rows = (y0..=y1).rev()
+                .map(|y| (x0..=x1).map(|x| population.get(x, y)).collect())
+                .collect();
+            (x1 - x0 + 1, y1 - y0 + 1, rows)
+        }
+    }
+}
+
+
+pub fn read(format: Format, text: &str) -> io::Result<Population> {
+    match format {
+        Format::Plaintext => read_plaintext(text),
+        Format::Life106 => read_life106(text),
+        Format::Rle => read_rle(text),
+        Format::Macrocell => read_macrocell(text),
+    }
+}
+
+pub fn write(format: Format, population: &Population) -> String {
+    match format {
+        Format::Plaintext => write_plaintext(population),
+        Format::Life106 => write_life106(population),
+        Format::Rle => write_rle(population),
+        Format::Macrocell => write_macrocell(population),
+    }
+}
+
+pub fn load(path: &Path) -> io::Result<Population> {
+    read(Format::from_path(path)?, &fs::read_to_string(path)?)
+}
+
+pub fn save(path: &Path, population: &Population) -> io::Result<()> {
+    fs::write(path, write(Format::from_path(path)?, population))
+}
+
+
+// plaintext (.cells) \\
+fn read_plaintext(text: &str) -> io::Result<Population> {
+    let mut cells = Vec::new();
+    let lines = text.lines().filter(|line| !line.starts_with('!'));
+    for (y, line) in lines.enumerate() {
+        for (x, c) in line.trim_end().chars().enumerate() {
+            match c {
+                'O' | 'o' | '*' => cells.push((x, y)),
+                '.' => {}
+                _ => return Err(invalid(format!("unexpected '{}' in plaintext pattern", c))),
+            }
+        }
+    }
+    Ok(from_rows(&cells))
+}
+
+fn write_plaintext(population: &Population) -> String {
+    let (_, _, rows) = to_rows(population);
+    let mut text = String::from("!Name: game_collection\n");
+    for row in rows {
+        let line: String = row.iter().map(|alive| if *alive {'O'} else {'.'}).collect();
+        text.push_str(line.trim_end_matches('.'));
+        text.push('\n');
+    }
+    text
+}
+
+
+// Life 1.06 \\
+fn read_life106(text: &str) -> io::Result<Population> {
+    let mut coords: Vec<(i64, i64)> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut numbers = line.split_whitespace().map(str::parse::<i64>);
+        match (numbers.next(), numbers.next(), numbers.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => coords.push((x, y)),
+            _ => return Err(invalid(format!("bad Life 1.06 line: {}", line))),
+        }
+    }
+    let min_x = coords.iter().map(|(x, _)| *x).min().unwrap_or(0);
+    let min_y = coords.iter().map(|(_, y)| *y).min().unwrap_or(0);
+    let cells: Vec<(usize, usize)> = coords.iter()
+        .map(|(x, y)| ((x - min_x) as usize, (y - min_y) as usize))
+        .collect();
+    Ok(from_rows(&cells))
+}
+
+fn write_life106(population: &Population) -> String {
+    let (_, _, rows) = to_rows(population);
+    let mut text = String::from("#Life 1.06\n");
+    for (y, row) in rows.iter().enumerate() {
+        for (x, alive) in row.iter().enumerate() {
+            if *alive {
+                text.push_str(&format!("{} {}\n", x, y));
+            }
+        }
+    }
+    text
+}
+
+
+// longest run of live cells read at once, against files asking for billions of cells \\
+const MAX_LIVE_RUN: usize = 1 << 20;
+
+// run length encoded (.rle) \\
+fn read_rle(text: &str) -> io::Result<Population> {
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut count = String::new();
+    let mut header_found = false;
+    let mut rule = Rule::default();
+    // runs stay within the declared size, and coordinates within an i64 \\
+    let (mut width, mut height) = (i64::MAX as usize, i64::MAX as usize);
+    let end = |start: usize, run: usize, limit: usize| start.checked_add(run).filter(|&end| end <= limit);
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !header_found && line.starts_with('x') {
+            header_found = true;
+            let declared = |name: &str| line.split(',')
+                .filter_map(|field| field.split_once('='))
+                .find(|(key, _)| key.trim() == name)
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok());
+            width = declared("x").map_or(width, |x| x.min(width));
+            height = declared("y").map_or(height, |y| y.min(height));
+            // the rule runs to the end of the line, since a bounded grid suffix has commas too \\
+            let field = line.match_indices(',').map(|(idx, _)| &line[idx + 1..]).find(|rest| rest.trim_start().starts_with("rule"));
+            if let Some((_, value)) = field.and_then(|field| field.split_once('=')) {
+                rule = value.parse()?;
+            }
+            continue;
+        }
+        for c in line.chars() {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+            let run: usize = if count.is_empty() {1} else {count.parse().map_err(|_| invalid(count.clone()))?};
+            count.clear();
+            let too_long = || invalid(format!("RLE run of {} cells does not fit in the pattern", run));
+            match c {
+                'b' | '.' => x = end(x, run, width).ok_or_else(too_long)?,
+                'o' | 'A'..='Z' => {
+                    let stop = end(x, run, width).filter(|_| run <= MAX_LIVE_RUN && y < height).ok_or_else(too_long)?;
+                    cells.extend((x..stop).map(|cx| (cx, y)));
+                    x = stop;
+                }
+                '$' => {
+                    y = end(y, run, height).ok_or_else(too_long)?;
+                    x = 0;
+                }
+                '!' => return Ok(Population {rule, ..from_rows(&cells)}),
+                c if c.is_whitespace() => {}
+                _ => return Err(invalid(format!("unexpected '{}' in RLE pattern", c))),
+            }
+        }
+    }
+    Ok(Population {rule, ..from_rows(&cells)})
+}
+
+fn write_rle(population: &Population) -> String {
+    let (width, height, rows) = to_rows(population);
+
+    // tokens of the form (run, tag) \\
+    let mut tokens: Vec<(usize, char)> = Vec::new();
+    let push = |tokens: &mut Vec<(usize, char)>, tag: char, run: usize| {
+        match tokens.last_mut() {
+            Some((n, t)) if *t == tag => *n += run,
+            _ => tokens.push((run, tag)),
+        }
+    };
+    for row in &rows {
+        let last_alive = row.iter().rposition(|alive| *alive);
+        for alive in &row[..last_alive.map_or(0, |i| i + 1)] {
+            push(&mut tokens, if *alive {'o'} else {'b'}, 1);
+        }
+        push(&mut tokens, '$', 1);
+    }
+    while let Some((_, '$')) = tokens.last() {
+        tokens.pop();
+    }
+    tokens.push((1, '!'));
+
+    let mut text = format!("x = {}, y = {}, rule = {}\n", width, height, population.rule);
+    let mut line = String::new();
+    for (run, tag) in tokens {
+        let token = if run > 1 {format!("{}{}", run, tag)} else {tag.to_string()};
+        if line.len() + token.len() > 70 {
+            text.push_str(&line);
+            text.push('\n');
+            line.clear();
+        }
+        line.push_str(&token);
+    }
+    text.push_str(&line);
+    text.push('\n');
+    text
+}
+
+
+// macrocell (.mc) \\
+// Each line after the header defines the next node of a quadtree, numbered
+// from 1: either an 8x8 leaf drawn with '.', '*' and '$', or a line
+// "level nw ne sw se" referring to earlier nodes, 0 being an empty node.
+// The last node is the root. Patterns are loaded straight into a hashlife
+// universe since they are often far too large for any other engine.
+fn read_macrocell(text: &str) -> io::Result<Population> {
+    let mut hashlife = HashLife::new();
+    let mut nodes: Vec<NodeId> = Vec::new();
+    let mut rule = Rule::default();
+
+    for line in text.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("#R") {
+            rule = value.parse()?;
+            continue;
+        }
+        if line.is_empty() || line.starts_with('[') || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with(['.', '*', '$']) {
+            let mut grid = [[false; 8]; 8];
+            let (mut x, mut row) = (0, 0);
+            for c in line.chars() {
+                match c {
+                    '.' => x += 1,
+                    '*' if x < 8 && row < 8 => {
+                        grid[row][x] = true;
+                        x += 1;
+                    }
+                    '$' => {
+                        row += 1;
+                        x = 0;
+                    }
+                    _ => return Err(invalid(format!("bad macrocell leaf: {}", line))),
+                }
+            }
+            nodes.push(leaf(&hashlife, &grid, 0, 0, 8));
+            continue;
+        }
+
+        let numbers: Vec<usize> = line.split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid(format!("bad macrocell node: {}", line)))?;
+        let (level, indices) = match numbers[..] {
+            [level, ..] if level > MAX_LEVEL as usize => return Err(invalid(format!("macrocell level {} is above the supported {}", level, MAX_LEVEL))),
+            [level, nw, ne, sw, se] if level > 3 => (level as u8, [nw, ne, sw, se]),
+            _ => return Err(invalid(format!("bad macrocell node: {}", line))),
+        };
+        let mut children = [DEAD; 4];
+        for (child, idx) in children.iter_mut().zip(indices) {
+            *child = match idx {
+                0 => hashlife.empty_node(level - 1),
+                idx if idx <= nodes.len() && hashlife.node(nodes[idx - 1]).0 == level - 1 => nodes[idx - 1],
+                _ => return Err(invalid(format!("bad macrocell node: {}", line))),
+            };
+        }
+        nodes.push(hashlife.join(children));
+    }
+
+    if let Some(root) = nodes.last() {
+        hashlife.set_root(*root);
+    }
+    Ok(Population {rule, ..Population::from_hashlife(hashlife)})
+}
+
+// quadtree of a square of the grid, given top-down rows \\
+fn leaf(hashlife: &HashLife, grid: &[[bool; 8]; 8], x: usize, row: usize, size: usize) -> NodeId {
+    if size == 1 {
+        return if grid[row][x] {ALIVE} else {DEAD};
+    }
+    let half = size / 2;
+    hashlife.join([
+        leaf(hashlife, grid, x, row, half),
+        leaf(hashlife, grid, x + half, row, half),
+        leaf(hashlife, grid, x, row + half, half),
+        leaf(hashlife, grid, x + half, row + half, half),
+    ])
+}
+
+fn write_macrocell(population: &Population) -> String {
+    let hashlife = match &population.engine {
+        Engine::HashLife(hashlife) => hashlife.clone(),
+        Engine::Packed(_) => {
+            let mut hashlife = HashLife::new();
+            for (x, y) in population.live_cells() {
+                hashlife.set(x, y, true);
+            }
+            hashlife
+        }
+    };
+    let mut lines = vec![String::from("[M2] (game_collection)"), format!("#R {}", population.rule)];
+    let mut index = HashMap::new();
+    write_node(&hashlife, hashlife.root(), &mut index, &mut lines);
+    lines.join("\n") + "\n"
+}
+
+// appends a node after its children and returns its number \\
+fn write_node(hashlife: &HashLife, id: NodeId, index: &mut HashMap<NodeId, usize>, lines: &mut Vec<String>) -> usize {
+    let (level, population, children) = hashlife.node(id);
+    if population == 0 {
+        return 0;
+    }
+    if let Some(number) = index.get(&id) {
+        return *number;
+    }
+    let line = if level == 3 {
+        let mut line = String::new();
+        for y in (0..8).rev() {
+            let row: String = (0..8).map(|x| if hashlife.node_cell(id, x, y) {'*'} else {'.'}).collect();
+            line.push_str(row.trim_end_matches('.'));
+            line.push('$');
+        }
+        line.trim_end_matches('$').to_string() + "$"
+    } else {
+        let numbers: Vec<String> = children.iter()
+            .map(|child| write_node(hashlife, *child, index, lines).to_string())
+            .collect();
+        format!("{} {}", level, numbers.join(" "))
+    };
+    lines.push(line);
+    index.insert(id, index.len() + 1);
+    index.len()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // glider, with a lone cell off to the side to keep blank rows and columns in play \\
+    const CELLS: [(i64, i64); 6] = [(1, 4), (2, 3), (0, 2), (1, 2), (2, 2), (6, 0)];
+
+    fn population(cells: &[(i64, i64)]) -> Population {
+        let mut population = Population::new();
+        for &(x, y) in cells {
+            population.set(x, y, true);
+        }
+        population
+    }
+
+    // live cells moved so the bounding box starts at the origin, sorted \\
+    fn normalized(population: &Population) -> Vec<(i64, i64)> {
+        let (x0, y0, _, _) = population.bounding_box().unwrap_or((0, 0, 0, 0));
+        let mut cells: Vec<(i64, i64)> = population.live_cells().iter().map(|&(x, y)| (x - x0, y - y0)).collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn round_trip(format: Format) {
+        let original = population(&CELLS);
+        let text = write(format, &original);
+        let read_back = read(format, &text).unwrap();
+        assert_eq!(normalized(&read_back), normalized(&original), "{:?}:\n{}", format, text);
+    }
+
+    #[test]
+    fn plaintext_round_trip() {
+        round_trip(Format::Plaintext);
+    }
+
+    #[test]
+    fn life106_round_trip() {
+        round_trip(Format::Life106);
+    }
+
+    #[test]
+    fn rle_round_trip() {
+        round_trip(Format::Rle);
+    }
+
+    #[test]
+    fn macrocell_round_trip() {
+        round_trip(Format::Macrocell);
+    }
+
+    #[test]
+    fn rejects_macrocell_levels_out_of_range() {
+        let node = format!("[M2] (golly 4.0)\n{} 0 0 0 0\n", MAX_LEVEL + 1);
+        assert!(read(Format::Macrocell, &node).is_err());
+        assert!(read(Format::Macrocell, "[M2] (golly 4.0)\n300 0 0 0 0\n").is_err());
+        assert!(read(Format::Macrocell, &format!("[M2] (golly 4.0)\n{} 0 0 0 0\n", MAX_LEVEL)).is_ok());
+    }
+
+    #[test]
+    fn reads_rle_rule_headers() {
+        let highlife: Rule = "B36/S23".parse().unwrap();
+        for header in ["x = 2, y = 1, rule = B36/S23", "x=2,y=1,rule=B36/S23", "x = 2, y = 1, rule = B36/S23:T100,80", "x = 2, y = 1, rule = 23/36 "] {
+            let population = read(Format::Rle, &format!("{}\n2o!\n", header)).unwrap();
+            assert!(population.rule == highlife, "{}", header);
+            assert_eq!(population.count(), 2, "{}", header);
+        }
+        let life = read(Format::Rle, "x = 2, y = 1, rule = Life\n2o!\n").unwrap();
+        assert!(life.rule.is_conway());
+        let missing = read(Format::Rle, "x = 2, y = 1\n2o!\n").unwrap();
+        assert!(missing.rule.is_conway());
+        let err = read(Format::Rle, "x = 2, y = 1, rule = B3/X23 \n2o!\n").err().unwrap();
+        assert_eq!(err.to_string(), "invalid rule 'B3/X23'");
+    }
+
+    #[test]
+    fn formats_agree_on_a_glider() {
+        let glider = normalized(&population(&[(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)]));
+        let plaintext = read(Format::Plaintext, "!Name: Glider\n.O\n..O\nOOO\n").unwrap();
+        let life106 = read(Format::Life106, "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n").unwrap();
+        let rle = read(Format::Rle, "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n").unwrap();
+        assert_eq!(normalized(&plaintext), glider);
+        assert_eq!(normalized(&life106), glider);
+        assert_eq!(normalized(&rle), glider);
+    }
+
+    #[test]
+    fn empty_patterns_round_trip() {
+        for format in [Format::Plaintext, Format::Life106, Format::Rle] {
+            let text = write(format, &Population::new());
+            assert_eq!(read(format, &text).unwrap().count(), 0, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_characters() {
+        assert!(read(Format::Plaintext, "O?O\n").is_err());
+        assert!(read(Format::Life106, "1 2 3\n").is_err());
+        assert!(read(Format::Rle, "x = 1, y = 1\nq!\n").is_err());
+    }
+
+    #[test]
+    fn rejects_runs_past_the_pattern() {
+        assert!(read(Format::Rle, "x = 3, y = 1\n999999999999o!\n").is_err());
+        assert!(read(Format::Rle, "x = 3, y = 1\n2b2o!\n").is_err());
+        assert!(read(Format::Rle, "x = 3, y = 2\n3o$o$o!\n").is_err());
+        assert!(read(Format::Rle, &format!("{}b2o!\n", usize::MAX - 1)).is_err());
+        assert!(read(Format::Rle, &format!("{}$o!\n", i64::MAX)).is_err());
+        assert!(read(Format::Rle, "x = 3, y = 2\n3o$3o$!\n").is_ok());
+        assert_eq!(read(Format::Rle, "1000000b2o!\n").unwrap().count(), 2);
+    }
+}
@@ -0,0 +1,639 @@
+use std::{cell::{Cell, RefCell}, collections::HashMap, rc::{Rc, Weak}};
+use super::rules::Rule;
+
+// Quadtree nodes are interned in a shared arena and never modified, so a
+// universe is just a root id and cloning one (e.g. for the undo history)
+// costs nothing. The root is always centered on the origin: a root of
+// level n covers [-2^(n-1), 2^(n-1)) on both axes, with y growing upward.
+//
+// Every universe registers its root with the arena. Once the arena has
+// grown past its limit, stepping sweeps the nodes no registered root
+// reaches, along with their cached results and bounds, and renumbers the
+// rest. Node ids handed out by `join` are only valid until the next step.
+
+pub type NodeId = u32;
+
+pub const DEAD: NodeId = 0;
+pub const ALIVE: NodeId = 1;
+// largest root whose coordinates still fit in an i64 \\
+pub const MAX_LEVEL: u8 = 62;
+// nodes the arena holds before its first collection \\
+const NODE_LIMIT: usize = 1 << 20;
+
+// children are ordered nw, ne, sw, se \\
+#[derive(Clone, Copy)]
+struct Node {
+    children: [NodeId; 4],
+    level: u8,
+    population: u64,
+}
+
+
+struct Arena {
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    bounds: HashMap<NodeId, (i64, i64, i64, i64)>,
+    tiles: HashMap<NodeId, [u64; 64]>,
+    empty: Vec<NodeId>,
+    rule: Rule,
+    roots: Vec<Weak<Cell<NodeId>>>,
+    limit: usize,
+}
+
+impl Arena {
+    fn new() -> Arena {
+        Arena {
+            nodes: vec![
+                Node {children: [DEAD; 4], level: 0, population: 0},
+                Node {children: [DEAD; 4], level: 0, population: 1},
+            ],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            bounds: HashMap::new(),
+            tiles: HashMap::new(),
+            empty: vec![DEAD],
+            rule: Rule::default(),
+            roots: Vec::new(),
+            limit: NODE_LIMIT,
+        }
+    }
+
+    // dead handles are dropped whenever the list would have to grow \\
+    fn register(&mut self, root: &Rc<Cell<NodeId>>) {
+        if self.roots.len() == self.roots.capacity() {
+            self.roots.retain(|root| root.strong_count() > 0);
+        }
+        self.roots.push(Rc::downgrade(root));
+    }
+
+    // keeps the nodes reachable from a registered root or an empty node,
+    // renumbered in their original order so children still come first \\
+    fn collect_garbage(&mut self) {
+        self.roots.retain(|root| root.strong_count() > 0);
+        let roots: Vec<Rc<Cell<NodeId>>> = self.roots.iter().filter_map(Weak::upgrade).collect();
+        let mut live = vec![false; self.nodes.len()];
+        live[DEAD as usize] = true;
+        live[ALIVE as usize] = true;
+        let mut stack: Vec<NodeId> = self.empty.iter().copied().chain(roots.iter().map(|root| root.get())).collect();
+        while let Some(id) = stack.pop() {
+            if !live[id as usize] {
+                live[id as usize] = true;
+                stack.extend(self.node(id).children);
+            }
+        }
+
+        let mut remap = vec![NodeId::MAX; self.nodes.len()];
+        let mut nodes = Vec::new();
+        for (id, node) in self.nodes.iter().enumerate() {
+            if live[id] {
+                remap[id] = nodes.len() as NodeId;
+                nodes.push(Node {children: node.children.map(|child| remap[child as usize]), ..*node});
+            }
+        }
+        let kept = |id: NodeId| Some(remap[id as usize]).filter(|&id| id != NodeId::MAX);
+        self.results = self.results.drain()
+            .filter_map(|((id, j), result)| Some(((kept(id)?, j), kept(result)?)))
+            .collect();
+        self.bounds = self.bounds.drain().filter_map(|(id, bounds)| Some((kept(id)?, bounds))).collect();
+        self.tiles = self.tiles.drain().filter_map(|(id, rows)| Some((kept(id)?, rows))).collect();
+        self.index = nodes.iter().enumerate()
+            .filter(|(_, node)| node.level > 0)
+            .map(|(id, node)| (node.children, id as NodeId))
+            .collect();
+        for id in self.empty.iter_mut() {
+            *id = remap[*id as usize];
+        }
+        for root in &roots {
+            root.set(remap[root.get() as usize]);
+        }
+        self.limit = NODE_LIMIT.max(nodes.len() * 2);
+        self.nodes = nodes;
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.index.get(&children) {
+            return id;
+        }
+        let level = self.node(children[0]).level + 1;
+        let population = children.iter().map(|c| self.node(*c).population).sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {children, level, population});
+        self.index.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let next = self.join([e; 4]);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    // node of level n-1 at the center of a node of level n \\
+    fn center(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.node(id).children;
+        self.join([
+            self.node(nw).children[3],
+            self.node(ne).children[2],
+            self.node(sw).children[1],
+            self.node(se).children[0],
+        ])
+    }
+
+    fn cell(&self, id: NodeId, x: i64, y: i64) -> bool {
+        let node = self.node(id);
+        if node.level == 0 {
+            return id == ALIVE;
+        }
+        let half = 1 << (node.level - 1);
+        let quadrant = match (x >= half, y >= half) {
+            (false, true) => 0,
+            (true, true) => 1,
+            (false, false) => 2,
+            (true, false) => 3,
+        };
+        self.cell(node.children[quadrant], x % half, y % half)
+    }
+
+    // one generation of the 2x2 center of a 4x4 node \\
+    fn base_step(&mut self, id: NodeId) -> NodeId {
+        let next = |x: i64, y: i64| {
+            let mut live_neighbors = 0;
+            for i in -1..=1 {
+                for j in -1..=1 {
+                    if (i != 0 || j != 0) && self.cell(id, x + i, y + j) {
+                        live_neighbors += 1;
+                    }
+                }
+            }
+            if self.rule.next_state(self.cell(id, x, y), live_neighbors) {ALIVE} else {DEAD}
+        };
+        let children = [next(1, 2), next(2, 2), next(1, 1), next(2, 1)];
+        self.join(children)
+    }
+
+    // center of a node of level n advanced by 2^j generations, with j <= n-2 \\
+    fn result(&mut self, id: NodeId, j: u8) -> NodeId {
+        let node = self.node(id);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+        let result = if node.level == 2 {
+            self.base_step(id)
+        } else {
+            let [nw, ne, sw, se] = node.children;
+            let [_, nw_ne, nw_sw, nw_se] = self.node(nw).children;
+            let [ne_nw, _, ne_sw, ne_se] = self.node(ne).children;
+            let [sw_nw, sw_ne, _, sw_se] = self.node(sw).children;
+            let [se_nw, se_ne, se_sw, _] = self.node(se).children;
+
+            // nine overlapping nodes of level n-1 \\
+            let subnodes = [
+                nw,
+                self.join([nw_ne, ne_nw, nw_se, ne_sw]),
+                ne,
+                self.join([nw_sw, nw_se, sw_nw, sw_ne]),
+                self.join([nw_se, ne_sw, sw_ne, se_nw]),
+                self.join([ne_sw, ne_se, se_nw, se_ne]),
+                sw,
+                self.join([sw_ne, se_nw, sw_se, se_sw]),
+                se,
+            ];
+
+            // at full speed both halves of the jump run here, otherwise
+            // the first half is skipped and only the second one advances \\
+            let full_speed = j == node.level - 2;
+            let mut r = [DEAD; 9];
+            for (i, subnode) in subnodes.iter().enumerate() {
+                r[i] = if full_speed {self.result(*subnode, j - 1)} else {self.center(*subnode)};
+            }
+            let j = if full_speed {j - 1} else {j};
+            let quadrants = [
+                self.join([r[0], r[1], r[3], r[4]]),
+                self.join([r[1], r[2], r[4], r[5]]),
+                self.join([r[3], r[4], r[6], r[7]]),
+                self.join([r[4], r[5], r[7], r[8]]),
+            ];
+            let mut q = [DEAD; 4];
+            for (i, quadrant) in quadrants.iter().enumerate() {
+                q[i] = self.result(*quadrant, j);
+            }
+            self.join(q)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    // (min_x, min_y, max_x, max_y) of the live cells relative to the node \\
+    fn bounds(&mut self, id: NodeId) -> Option<(i64, i64, i64, i64)> {
+        let node = self.node(id);
+        if node.population == 0 {
+            return None;
+        }
+        if node.level == 0 {
+            return Some((0, 0, 0, 0));
+        }
+        if let Some(&bounds) = self.bounds.get(&id) {
+            return Some(bounds);
+        }
+        let half = 1 << (node.level - 1);
+        let offsets = [(0, half), (half, half), (0, 0), (half, 0)];
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+        for (child, (ox, oy)) in node.children.iter().zip(offsets) {
+            if let Some((x0, y0, x1, y1)) = self.bounds(*child) {
+                let (x0, y0, x1, y1) = (x0 + ox, y0 + oy, x1 + ox, y1 + oy);
+                bounds = Some(match bounds {
+                    None => (x0, y0, x1, y1),
+                    Some((a0, b0, a1, b1)) => (a0.min(x0), b0.min(y0), a1.max(x1), b1.max(y1)),
+                });
+            }
+        }
+        self.bounds.insert(id, bounds.unwrap());
+        bounds
+    }
+
+    fn collect(&self, id: NodeId, x0: i64, y0: i64, rect: (i64, i64, i64, i64), cells: &mut Vec<(i64, i64)>) {
+        let node = self.node(id);
+        let size = 1 << node.level;
+        let (rx0, ry0, rx1, ry1) = rect;
+        if node.population == 0 || x0 > rx1 || y0 > ry1 || x0 + size <= rx0 || y0 + size <= ry0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push((x0, y0));
+            return;
+        }
+        let half = size / 2;
+        let offsets = [(0, half), (half, half), (0, 0), (half, 0)];
+        for (child, (ox, oy)) in node.children.iter().zip(offsets) {
+            self.collect(*child, x0 + ox, y0 + oy, rect, cells);
+        }
+    }
+
+    // the level 6 nodes holding live cells, as bitgrid tiles: one u64 per row,
+    // bit x holding the cell at column x \\
+    fn tiles(&mut self, id: NodeId, x0: i64, y0: i64, f: &mut impl FnMut((i64, i64), &[u64; 64])) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 6 {
+            let rows = match self.tiles.get(&id) {
+                Some(rows) => *rows,
+                None => {
+                    let mut rows = [0; 64];
+                    self.fill_rows(id, 0, 0, &mut rows);
+                    self.tiles.insert(id, rows);
+                    rows
+                }
+            };
+            f((x0.div_euclid(64), y0.div_euclid(64)), &rows);
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        let offsets = [(0, half), (half, half), (0, 0), (half, 0)];
+        for (child, (ox, oy)) in node.children.iter().zip(offsets) {
+            self.tiles(*child, x0 + ox, y0 + oy, f);
+        }
+    }
+
+    fn fill_rows(&self, id: NodeId, x: usize, y: usize, rows: &mut [u64; 64]) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            rows[y] |= 1 << x;
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        let offsets = [(0, half), (half, half), (0, 0), (half, 0)];
+        for (child, (ox, oy)) in node.children.iter().zip(offsets) {
+            self.fill_rows(*child, x + ox, y + oy, rows);
+        }
+    }
+
+    // cells alive in `a` but not in `b`, two nodes of the same level \\
+    fn new_cells(&self, a: NodeId, b: NodeId, memo: &mut HashMap<(NodeId, NodeId), u64>) -> u64 {
+        let (node_a, node_b) = (self.node(a), self.node(b));
+        if a == b || node_a.population == 0 {
+            return 0;
+        }
+        if node_b.population == 0 {
+            return node_a.population;
+        }
+        if let Some(&count) = memo.get(&(a, b)) {
+            return count;
+        }
+        let count = (0..4).map(|i| self.new_cells(node_a.children[i], node_b.children[i], memo)).sum();
+        memo.insert((a, b), count);
+        count
+    }
+
+    fn with_cell(&mut self, id: NodeId, x: i64, y: i64, alive: bool) -> NodeId {
+        let node = self.node(id);
+        if node.level == 0 {
+            return if alive {ALIVE} else {DEAD};
+        }
+        let half = 1 << (node.level - 1);
+        let quadrant = match (x >= half, y >= half) {
+            (false, true) => 0,
+            (true, true) => 1,
+            (false, false) => 2,
+            (true, false) => 3,
+        };
+        let mut children = node.children;
+        children[quadrant] = self.with_cell(children[quadrant], x % half, y % half, alive);
+        self.join(children)
+    }
+}
+
+
+pub struct HashLife {
+    arena: Rc<RefCell<Arena>>,
+    root: Rc<Cell<NodeId>>,
+}
+
+impl Clone for HashLife {
+    fn clone(&self) -> HashLife {
+        HashLife::with_root(&self.arena, self.root.get())
+    }
+}
+
+impl HashLife {
+    pub fn new() -> HashLife {
+        let mut arena = Arena::new();
+        let root = arena.empty(3);
+        HashLife::with_root(&Rc::new(RefCell::new(arena)), root)
+    }
+
+    fn with_root(arena: &Rc<RefCell<Arena>>, root: NodeId) -> HashLife {
+        let root = Rc::new(Cell::new(root));
+        arena.borrow_mut().register(&root);
+        HashLife {arena: Rc::clone(arena), root}
+    }
+
+    pub fn level(&self) -> u8 {
+        self.arena.borrow().node(self.root.get()).level
+    }
+
+    fn half(&self) -> i64 {
+        1 << (self.level() - 1)
+    }
+
+    // doubles the root size, keeping the pattern centered; false once the
+    // root is as large as i64 coordinates allow \\
+    fn expand(&mut self) -> bool {
+        let mut arena = self.arena.borrow_mut();
+        let node = arena.node(self.root.get());
+        if node.level >= MAX_LEVEL {
+            return false;
+        }
+        let e = arena.empty(node.level - 1);
+        let [nw, ne, sw, se] = node.children;
+        let children = [
+            arena.join([e, e, e, nw]),
+            arena.join([e, e, ne, e]),
+            arena.join([e, sw, e, e]),
+            arena.join([se, e, e, e]),
+        ];
+        self.root.set(arena.join(children));
+        true
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        if x < -half || y < -half || x >= half || y >= half {
+            return false;
+        }
+        self.arena.borrow().cell(self.root.get(), x + half, y + half)
+    }
+
+    // cells beyond the reach of the largest root are left out \\
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        while x < -self.half() || y < -self.half() || x >= self.half() || y >= self.half() {
+            if !alive || !self.expand() {
+                return;
+            }
+        }
+        let half = self.half();
+        self.root.set(self.arena.borrow_mut().with_cell(self.root.get(), x + half, y + half, alive));
+    }
+
+    pub fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        let half = self.half();
+        let (x0, y0, x1, y1) = self.arena.borrow_mut().bounds(self.root.get())?;
+        Some((x0 - half, y0 - half, x1 - half, y1 - half))
+    }
+
+    // calls `f` with every 64x64 tile holding live cells, tile (tx, ty) starting at (64 tx, 64 ty) \\
+    pub fn for_each_tile(&self, mut f: impl FnMut((i64, i64), &[u64; 64])) {
+        let mut universe = self.clone();
+        while universe.level() < 7 {
+            universe.expand();
+        }
+        let half = universe.half();
+        self.arena.borrow_mut().tiles(universe.root.get(), -half, -half, &mut f);
+    }
+
+    pub fn cells_in(&self, x0: i64, y0: i64, x1: i64, y1: i64) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        let half = self.half();
+        self.arena.borrow().collect(self.root.get(), -half, -half, (x0, y0, x1, y1), &mut cells);
+        cells
+    }
+
+    // results computed under another rule are dropped \\
+    pub fn set_rule(&mut self, rule: Rule) {
+        let mut arena = self.arena.borrow_mut();
+        if arena.rule != rule {
+            arena.rule = rule;
+            arena.results.clear();
+        }
+    }
+
+    // advances the universe by 2^j generations; a pattern reaching the edge
+    // of the largest root loses the cells that would leave it \\
+    pub fn step(&mut self, j: u8) {
+        loop {
+            let mut arena = self.arena.borrow_mut();
+            let level = arena.node(self.root.get()).level;
+            let center = arena.center(self.root.get());
+            let inner = arena.center(center);
+            if level >= j + 3 && arena.node(inner).population == arena.node(self.root.get()).population {
+                break;
+            }
+            drop(arena);
+            if !self.expand() {
+                break;
+            }
+        }
+        let mut arena = self.arena.borrow_mut();
+        self.root.set(arena.result(self.root.get(), j));
+        if arena.nodes.len() > arena.limit {
+            arena.collect_garbage();
+        }
+    }
+
+    pub fn population(&self) -> u64 {
+        self.arena.borrow().node(self.root.get()).population
+    }
+
+    // cells alive here but not in `before`, which must share the same arena \\
+    pub fn new_cells(&self, before: &HashLife) -> Option<u64> {
+        if !Rc::ptr_eq(&self.arena, &before.arena) {
+            return None;
+        }
+        let (mut after, mut before) = (self.clone(), before.clone());
+        while after.level() < before.level() {
+            after.expand();
+        }
+        while before.level() < after.level() {
+            before.expand();
+        }
+        Some(self.arena.borrow().new_cells(after.root.get(), before.root.get(), &mut HashMap::new()))
+    }
+
+    pub fn same_cells(&self, other: &HashLife) -> bool {
+        Rc::ptr_eq(&self.arena, &other.arena) && self.root.get() == other.root.get()
+    }
+
+    // node level building blocks for the macrocell format \\
+    pub fn empty_node(&self, level: u8) -> NodeId {
+        self.arena.borrow_mut().empty(level)
+    }
+
+    pub fn join(&self, children: [NodeId; 4]) -> NodeId {
+        self.arena.borrow_mut().join(children)
+    }
+
+    // cell of a node, relative to its bottom left corner \\
+    pub fn node_cell(&self, id: NodeId, x: i64, y: i64) -> bool {
+        self.arena.borrow().cell(id, x, y)
+    }
+
+    pub fn node(&self, id: NodeId) -> (u8, u64, [NodeId; 4]) {
+        let node = self.arena.borrow().node(id);
+        (node.level, node.population, node.children)
+    }
+
+    pub fn root(&self) -> NodeId {
+        self.root.get()
+    }
+
+    pub fn set_root(&mut self, root: NodeId) {
+        self.root.set(root);
+        while self.level() < 3 {
+            self.expand();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Population, Engine};
+
+    // R-pentomino, which keeps changing for over a thousand generations \\
+    const R_PENTOMINO: [(i64, i64); 5] = [(1, 2), (2, 2), (0, 1), (1, 1), (1, 0)];
+
+    fn sorted(population: &Population) -> Vec<(i64, i64)> {
+        let mut cells = population.live_cells();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn pair() -> (Population, Population) {
+        let (mut packed, mut hashlife) = (Population::new(), Population::from_hashlife(HashLife::new()));
+        for (x, y) in R_PENTOMINO {
+            packed.set(x, y, true);
+            hashlife.set(x, y, true);
+        }
+        (packed, hashlife)
+    }
+
+    #[test]
+    fn matches_the_packed_engine_generation_by_generation() {
+        let (mut packed, mut hashlife) = pair();
+        for generation in 1..=200 {
+            packed.get_next_gen();
+            hashlife.get_next_gen();
+            assert_eq!(sorted(&hashlife), sorted(&packed), "generation {}", generation);
+        }
+    }
+
+    #[test]
+    fn jumps_match_the_packed_engine() {
+        let (mut packed, mut hashlife) = pair();
+        packed.jump(7);
+        hashlife.jump(7);
+        assert_eq!(hashlife.generation, 128);
+        assert_eq!(sorted(&hashlife), sorted(&packed));
+    }
+
+    #[test]
+    fn garbage_collection_keeps_every_registered_universe() {
+        let (_, mut population) = pair();
+        let mut kept = Vec::new();
+        for _ in 0..50 {
+            population.get_next_gen();
+            kept.push(population.clone());
+        }
+        let expected: Vec<Vec<(i64, i64)>> = kept.iter().map(sorted).collect();
+        drop(kept.drain(..25));
+
+        let Engine::HashLife(hashlife) = &population.engine else {unreachable!()};
+        let before = hashlife.arena.borrow().nodes.len();
+        hashlife.arena.borrow_mut().collect_garbage();
+        assert!(hashlife.arena.borrow().nodes.len() < before);
+
+        for (population, cells) in kept.iter().zip(&expected[25..]) {
+            assert_eq!(&sorted(population), cells);
+        }
+        let mut stepped = kept[0].clone();
+        let mut reference = Population::new();
+        for &(x, y) in &expected[25] {
+            reference.set(x, y, true);
+        }
+        stepped.get_next_gen();
+        reference.get_next_gen();
+        assert_eq!(sorted(&stepped), sorted(&reference));
+    }
+
+    #[test]
+    fn cells_past_the_largest_root_are_left_out() {
+        let mut hashlife = HashLife::new();
+        hashlife.set(i64::MAX / 2, 0, true);
+        assert!(!hashlife.get(i64::MAX / 2, 0));
+        assert_eq!(hashlife.population(), 0);
+        assert!(hashlife.level() <= MAX_LEVEL);
+
+        let edge = 1 << (MAX_LEVEL - 1);
+        hashlife.set(-edge, edge - 1, true);
+        assert!(hashlife.get(-edge, edge - 1));
+        assert_eq!(hashlife.level(), MAX_LEVEL);
+    }
+
+    #[test]
+    fn patterns_at_the_edge_of_the_largest_root_are_clipped() {
+        // a blinker against the right edge, which cannot grow any further \\
+        let edge = 1 << (MAX_LEVEL - 1);
+        let mut hashlife = HashLife::new();
+        for x in edge - 3..edge {
+            hashlife.set(x, 0, true);
+        }
+        hashlife.step(0);
+        assert!(hashlife.level() <= MAX_LEVEL);
+        assert_eq!(hashlife.population(), 0);
+    }
+}
@@ -0,0 +1,250 @@
+use std::{collections::VecDeque, mem};
+
+const CAPACITY: usize = 512;
+// bytes of saved states the undo ring may hold \\
+const MEMORY: usize = 256 << 20;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Change {
+    Edit,
+    Step,
+}
+
+
+// A state the history can take back. When the state can tell what changed
+// between two of its values, an entry keeps only that difference and
+// patches the state back and forth with it; otherwise the entry keeps a
+// whole copy.
+pub trait Revision: Sized {
+    type Diff;
+
+    // what turns `before` into `self`, none if only a copy will do \\
+    fn diff(&self, before: &Self) -> Option<Self::Diff>;
+    fn revert(&mut self, diff: &Self::Diff);
+    fn apply(&mut self, diff: &Self::Diff);
+    // rough size in bytes of a copy or a diff kept by the history \\
+    fn footprint(&self) -> usize;
+    fn diff_footprint(diff: &Self::Diff) -> usize;
+}
+
+
+enum Saved<T: Revision> {
+    Copy(T),
+    Diff(T::Diff),
+}
+
+struct Entry<T: Revision> {
+    change: Change,
+    saved: Saved<T>,
+    bytes: usize,
+}
+
+impl<T: Revision> Entry<T> {
+    fn new(change: Change, saved: Saved<T>) -> Entry<T> {
+        let bytes = match &saved {
+            Saved::Copy(state) => state.footprint(),
+            Saved::Diff(diff) => T::diff_footprint(diff),
+        };
+        Entry {change, saved, bytes}
+    }
+}
+
+
+// When the ring is full, in entries or in bytes, the oldest generation step
+// goes first so that edits survive a long simulation run, and dropping it
+// only makes an undo skip further back in time. That holds unless the entry
+// before it is a diff, which leads to the state the dropped step started
+// from; then the oldest entry goes instead. The newest entry is always
+// kept, however large.
+pub struct History<T: Revision> {
+    undo: VecDeque<Entry<T>>,
+    redo: Vec<Entry<T>>,
+    bytes: usize,
+}
+
+impl<T: Revision> History<T> {
+    pub fn new() -> History<T> {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            bytes: 0,
+        }
+    }
+
+    // `before` is the state the change started from, `after` where it led \\
+    pub fn record(&mut self, change: Change, before: T, after: &T) {
+        self.redo.clear();
+        let saved = match after.diff(&before) {
+            Some(diff) => Saved::Diff(diff),
+            None => Saved::Copy(before),
+        };
+        self.push(Entry::new(change, saved));
+        while self.undo.len() > 1 && (self.undo.len() > CAPACITY || self.bytes > MEMORY) {
+            let newest = self.undo.len() - 1;
+            let oldest = (0..newest).find(|&i| self.undo[i].change == Change::Step && !self.leads_to(i)).unwrap_or(0);
+            if let Some(entry) = self.undo.remove(oldest) {
+                self.bytes -= entry.bytes;
+            }
+        }
+    }
+
+    // whether the entry before `index` is a diff leading to the state it starts from \\
+    fn leads_to(&self, index: usize) -> bool {
+        index > 0 && matches!(self.undo[index - 1].saved, Saved::Diff(_))
+    }
+
+    fn push(&mut self, entry: Entry<T>) {
+        self.bytes += entry.bytes;
+        self.undo.push_back(entry);
+    }
+
+    pub fn undo(&mut self, state: &mut T) {
+        if let Some(entry) = self.undo.pop_back() {
+            self.bytes -= entry.bytes;
+            let saved = match entry.saved {
+                Saved::Copy(before) => Saved::Copy(mem::replace(state, before)),
+                Saved::Diff(diff) => {
+                    state.revert(&diff);
+                    Saved::Diff(diff)
+                }
+            };
+            self.redo.push(Entry::new(entry.change, saved));
+        }
+    }
+
+    pub fn redo(&mut self, state: &mut T) {
+        if let Some(entry) = self.redo.pop() {
+            let saved = match entry.saved {
+                Saved::Copy(after) => Saved::Copy(mem::replace(state, after)),
+                Saved::Diff(diff) => {
+                    state.apply(&diff);
+                    Saved::Diff(diff)
+                }
+            };
+            self.push(Entry::new(entry.change, saved));
+        }
+    }
+
+    // undoes the last change only if it was a generation step \\
+    pub fn step_back(&mut self, state: &mut T) {
+        if let Some(Entry {change: Change::Step, ..}) = self.undo.back() {
+            self.undo(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a number held in a state of a given size, diffed while the size stays \\
+    #[derive(Clone, Copy, PartialEq)]
+    struct Mark(i64, usize);
+
+    impl Revision for Mark {
+        type Diff = i64;
+
+        fn diff(&self, before: &Mark) -> Option<i64> {
+            if self.1 == before.1 {Some(self.0 - before.0)} else {None}
+        }
+
+        fn revert(&mut self, diff: &i64) {
+            self.0 -= diff;
+        }
+
+        fn apply(&mut self, diff: &i64) {
+            self.0 += diff;
+        }
+
+        fn footprint(&self) -> usize {
+            self.1
+        }
+
+        fn diff_footprint(_diff: &i64) -> usize {
+            1
+        }
+    }
+
+    // records a change of `state` to `value`, kept as a copy when the size changes \\
+    fn change(history: &mut History<Mark>, state: &mut Mark, change: Change, value: Mark) {
+        let before = mem::replace(state, value);
+        history.record(change, before, state);
+    }
+
+    fn undo_all(history: &mut History<Mark>, state: &mut Mark) -> usize {
+        let mut undone = 0;
+        while !history.undo.is_empty() {
+            history.undo(state);
+            undone += 1;
+        }
+        undone
+    }
+
+    #[test]
+    fn steps_are_dropped_before_edits() {
+        let mut history = History::new();
+        let mut state = Mark(-1, 1);
+        change(&mut history, &mut state, Change::Edit, Mark(0, 2));
+        for n in 0..CAPACITY as i64 + 10 {
+            change(&mut history, &mut state, Change::Step, Mark(n + 1, n as usize + 3));
+        }
+        assert_eq!(undo_all(&mut history, &mut state), CAPACITY);
+        assert_eq!(state.0, -1);
+    }
+
+    #[test]
+    fn diffs_are_dropped_in_order() {
+        let mut history = History::new();
+        let mut state = Mark(-1, 1);
+        change(&mut history, &mut state, Change::Edit, Mark(0, 1));
+        for n in 0..CAPACITY as i64 + 10 {
+            change(&mut history, &mut state, Change::Step, Mark(n + 1, 1));
+        }
+        assert_eq!(undo_all(&mut history, &mut state), CAPACITY);
+        assert_eq!(state.0, 10);
+    }
+
+    #[test]
+    fn the_ring_is_capped_by_memory() {
+        let mut history = History::new();
+        let mut state = Mark(0, 0);
+        let per_entry = MEMORY / 100;
+        for n in 0..110 {
+            change(&mut history, &mut state, Change::Step, Mark(n + 1, per_entry + n as usize));
+        }
+        assert!(history.bytes <= MEMORY);
+        assert_eq!(history.undo.len(), 99);
+        assert!(matches!(history.undo[0].saved, Saved::Copy(Mark(11, _))));
+    }
+
+    #[test]
+    fn the_newest_entry_is_kept_however_large() {
+        let mut history = History::new();
+        let mut state = Mark(0, 1);
+        change(&mut history, &mut state, Change::Step, Mark(1, 2 * MEMORY));
+        change(&mut history, &mut state, Change::Edit, Mark(2, 1));
+        assert_eq!(history.undo.len(), 1);
+        history.undo(&mut state);
+        assert_eq!(state.0, 1);
+    }
+
+    #[test]
+    fn undo_and_redo_go_through_copies_and_diffs() {
+        let mut history = History::new();
+        let mut state = Mark(1, 3);
+        change(&mut history, &mut state, Change::Edit, Mark(2, 5));
+        change(&mut history, &mut state, Change::Step, Mark(7, 5));
+        assert_eq!(history.bytes, 3 + 1);
+        history.undo(&mut state);
+        history.undo(&mut state);
+        assert!(state == Mark(1, 3));
+        assert_eq!(history.bytes, 0);
+        history.redo(&mut state);
+        assert!(state == Mark(2, 5));
+        assert_eq!(history.bytes, 3);
+        history.redo(&mut state);
+        assert!(state == Mark(7, 5));
+        assert_eq!(history.bytes, 3 + 1);
+    }
+}
@@ -0,0 +1,463 @@
+use std::{fs, io, path::Path};
+use tui::style::Color;
+use super::{Population, theme::Theme};
+
+// Boards are exported as pictures with square cells of `cell` pixels: PBM
+// (black cells on white, as the format expects), indexed PNG or animated
+// GIF. The encoders are written here rather than pulled in as crates; PNG
+// only uses the fixed Huffman codes of deflate, matching runs against the
+// previous pixel and the previous row, which is all a grid of cells needs.
+
+const MAX_DISTANCE: usize = 32768;
+const MAX_MATCH: usize = 258;
+const MAX_CODES: u16 = 4096;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Pbm,
+    Png,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> io::Result<ImageFormat> {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("pbm") => Ok(ImageFormat::Pbm),
+            Some("png") => Ok(ImageFormat::Png),
+            Some("gif") => Ok(ImageFormat::Gif),
+            _ => Err(invalid(format!("unknown image format: {}", path.display()))),
+        }
+    }
+}
+
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn rgb(color: Color) -> [u8; 3] {
+    match color {
+        Color::Rgb(r, g, b) => [r, g, b],
+        Color::White => [255, 255, 255],
+        Color::Gray => [192, 192, 192],
+        Color::DarkGray => [128, 128, 128],
+        _ => [0, 0, 0],
+    }
+}
+
+// picture of a region, one palette index per pixel, top row first \\
+struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Raster {
+    fn new(cells: &[(i64, i64)], (x0, y0, x1, y1): Rect, cell: usize) -> Raster {
+        let (width, height) = ((x1 - x0 + 1) as usize * cell, (y1 - y0 + 1) as usize * cell);
+        let mut pixels = vec![0; width * height];
+        for &(x, y) in cells {
+            let (left, top) = ((x - x0) as usize * cell, (y1 - y) as usize * cell);
+            for row in top..top + cell {
+                pixels[row * width + left..row * width + left + cell].fill(1);
+            }
+        }
+        Raster {width, height, pixels}
+    }
+}
+
+type Rect = (i64, i64, i64, i64);
+
+// board of a bounded population, live cells of an unbounded one \\
+fn region(population: &Population) -> Option<Rect> {
+    match population.size {
+        Some(_) => Some(population.frame()),
+        None => population.bounding_box(),
+    }
+}
+
+fn union(a: Option<Rect>, b: Option<Rect>) -> Option<Rect> {
+    match (a, b) {
+        (Some((x0, y0, x1, y1)), Some((a0, b0, a1, b1))) => Some((x0.min(a0), y0.min(b0), x1.max(a1), y1.max(b1))),
+        _ => a.or(b),
+    }
+}
+
+fn nothing_to_export() -> io::Error {
+    invalid(String::from("no live cells to export"))
+}
+
+// the population, or a region of it, as a PBM or PNG picture \\
+pub fn save_image(path: &Path, population: &Population, area: Option<Rect>, cell: usize, theme: &Theme) -> io::Result<()> {
+    let area = area.or_else(|| region(population)).ok_or_else(nothing_to_export)?;
+    let (x0, y0, x1, y1) = area;
+    let raster = Raster::new(&population.cells_in(x0, y0, x1, y1), area, cell.max(1));
+    match ImageFormat::from_path(path)? {
+        ImageFormat::Pbm => fs::write(path, pbm(&raster)),
+        ImageFormat::Png => fs::write(path, png(&raster, &[rgb(theme.background), rgb(theme.live)])),
+        ImageFormat::Gif => Err(invalid(String::from("GIF exports are animations, render some generations instead"))),
+    }
+}
+
+// `gens` generations after the current one as an animated GIF \\
+pub fn save_animation(path: &Path, population: &Population, area: Option<Rect>, gens: u64, cell: usize, delay: u16, theme: &Theme) -> io::Result<()> {
+    if ImageFormat::from_path(path)? != ImageFormat::Gif {
+        return Err(invalid(format!("animations are saved as GIF: {}", path.display())));
+    }
+    let mut population = population.clone();
+    let mut frames = vec![population.live_cells()];
+    let mut bounds = region(&population);
+    for _ in 0..gens {
+        population.get_next_gen();
+        frames.push(population.live_cells());
+        bounds = union(bounds, region(&population));
+    }
+    let area = area.or(bounds).ok_or_else(nothing_to_export)?;
+    let (x0, y0, x1, y1) = area;
+    let cell = cell.max(1);
+    if ((x1 - x0 + 1).max(y1 - y0 + 1) as usize).saturating_mul(cell) > u16::MAX as usize {
+        return Err(invalid(String::from("GIF frames are limited to 65535 pixels a side")));
+    }
+
+    let rasters: Vec<Raster> = frames.iter()
+        .map(|cells| {
+            let inside: Vec<(i64, i64)> = cells.iter().copied()
+                .filter(|&(x, y)| x >= x0 && y >= y0 && x <= x1 && y <= y1)
+                .collect();
+            Raster::new(&inside, area, cell)
+        })
+        .collect();
+    fs::write(path, gif(&rasters, &[rgb(theme.background), rgb(theme.live)], delay))
+}
+
+
+// binary PBM (P4), rows packed eight pixels to a byte \\
+fn pbm(raster: &Raster) -> Vec<u8> {
+    let mut bytes = format!("P4\n{} {}\n", raster.width, raster.height).into_bytes();
+    for row in raster.pixels.chunks(raster.width) {
+        for chunk in row.chunks(8) {
+            let byte = chunk.iter().enumerate().fold(0u8, |byte, (i, &pixel)| byte | pixel << (7 - i));
+            bytes.push(byte);
+        }
+    }
+    bytes
+}
+
+
+// bits packed from the least significant one, as deflate and GIF both want \\
+struct Bits {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits {
+    fn new() -> Bits {
+        Bits {bytes: Vec::new(), buffer: 0, count: 0}
+    }
+
+    fn write(&mut self, value: u32, count: u32) {
+        self.buffer |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes go most significant bit first \\
+    fn write_code(&mut self, code: u32, count: u32) {
+        let reversed = (0..count).fold(0, |reversed, bit| reversed << 1 | (code >> bit & 1));
+        self.write(reversed, count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| if crc & 1 == 1 {crc >> 1 ^ 0xedb88320} else {crc >> 1})
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
+
+// (symbol or code, extra bits, extra value) for a length or a distance \\
+fn length_code(length: usize) -> (u32, u32, u32) {
+    const BASES: [usize; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+    let idx = BASES.iter().rposition(|&base| base <= length).unwrap();
+    let extra = if idx < 8 || idx == 28 {0} else {(idx as u32 - 4) / 4};
+    (257 + idx as u32, extra, (length - BASES[idx]) as u32)
+}
+
+fn distance_code(distance: usize) -> (u32, u32, u32) {
+    let (mut base, mut code) = (1, 0);
+    loop {
+        let extra = if code < 4 {0} else {code / 2 - 1};
+        if distance < base + (1 << extra) {
+            return (code as u32, extra as u32, (distance - base) as u32);
+        }
+        base += 1 << extra;
+        code += 1;
+    }
+}
+
+fn write_symbol(bits: &mut Bits, symbol: u32) {
+    match symbol {
+        0..=143 => bits.write_code(0x30 + symbol, 8),
+        144..=255 => bits.write_code(0x190 + symbol - 144, 9),
+        256..=279 => bits.write_code(symbol - 256, 7),
+        _ => bits.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+// zlib stream in a single fixed Huffman block \\
+fn deflate(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut bits = Bits::new();
+    bits.write(1, 1);
+    bits.write(1, 2);
+    let mut pos = 0;
+    while pos < data.len() {
+        let longest = (data.len() - pos).min(MAX_MATCH);
+        let matched = |distance: usize| match distance <= pos && distance <= MAX_DISTANCE {
+            true => (0..longest).take_while(|&i| data[pos + i] == data[pos + i - distance]).count(),
+            false => 0,
+        };
+        let (length, distance) = [1, stride].into_iter()
+            .map(|distance| (matched(distance), distance))
+            .max()
+            .unwrap();
+        if length >= 3 {
+            let (symbol, extra, value) = length_code(length);
+            write_symbol(&mut bits, symbol);
+            bits.write(value, extra);
+            let (code, extra, value) = distance_code(distance);
+            bits.write_code(code, 5);
+            bits.write(value, extra);
+            pos += length;
+        } else {
+            write_symbol(&mut bits, data[pos] as u32);
+            pos += 1;
+        }
+    }
+    write_symbol(&mut bits, 256);
+
+    let mut stream = vec![0x78, 0x01];
+    stream.extend(bits.finish());
+    stream.extend(adler32(data).to_be_bytes());
+    stream
+}
+
+fn png(raster: &Raster, palette: &[[u8; 3]]) -> Vec<u8> {
+    let chunk = |bytes: &mut Vec<u8>, kind: &[u8], data: &[u8]| {
+        bytes.extend((data.len() as u32).to_be_bytes());
+        let start = bytes.len();
+        bytes.extend(kind);
+        bytes.extend(data);
+        let crc = crc32(&bytes[start..]);
+        bytes.extend(crc.to_be_bytes());
+    };
+    let mut header = Vec::new();
+    header.extend((raster.width as u32).to_be_bytes());
+    header.extend((raster.height as u32).to_be_bytes());
+    header.extend([8, 3, 0, 0, 0]);
+    // every row starts with filter type 0 \\
+    let mut data = Vec::with_capacity((raster.width + 1) * raster.height);
+    for row in raster.pixels.chunks(raster.width) {
+        data.push(0);
+        data.extend(row);
+    }
+
+    let mut bytes = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    chunk(&mut bytes, b"IHDR", &header);
+    chunk(&mut bytes, b"PLTE", &palette.concat());
+    chunk(&mut bytes, b"IDAT", &deflate(&data, raster.width + 1));
+    chunk(&mut bytes, b"IEND", &[]);
+    bytes
+}
+
+
+// variable width LZW codes, with a clear code once the table is full \\
+fn lzw(pixels: &[u8], min_size: u32) -> Vec<u8> {
+    let (clear, end) = (1u16 << min_size, (1u16 << min_size) + 1);
+    // code extending a prefix code by a pixel, 0 when there is none yet \\
+    let mut table = vec![0u16; MAX_CODES as usize * clear as usize];
+    let (mut size, mut next) = (min_size + 1, end + 1);
+    let mut bits = Bits::new();
+    bits.write(clear as u32, size);
+
+    let mut prefix = pixels[0] as u16;
+    for &pixel in &pixels[1..] {
+        let entry = prefix as usize * clear as usize + pixel as usize;
+        if table[entry] != 0 {
+            prefix = table[entry];
+            continue;
+        }
+        bits.write(prefix as u32, size);
+        if next < MAX_CODES {
+            table[entry] = next;
+            next += 1;
+            if next > 1 << size && size < 12 {
+                size += 1;
+            }
+        } else {
+            bits.write(clear as u32, size);
+            table.fill(0);
+            size = min_size + 1;
+            next = end + 1;
+        }
+        prefix = pixel as u16;
+    }
+    bits.write(prefix as u32, size);
+    bits.write(end as u32, size);
+    bits.finish()
+}
+
+fn gif(frames: &[Raster], palette: &[[u8; 3]], delay: u16) -> Vec<u8> {
+    let (width, height) = (frames[0].width as u16, frames[0].height as u16);
+    let mut bytes = b"GIF89a".to_vec();
+    bytes.extend(width.to_le_bytes());
+    bytes.extend(height.to_le_bytes());
+    // global table of two colors \\
+    bytes.extend([0x80, 0, 0]);
+    bytes.extend(palette.concat());
+    // loop forever \\
+    bytes.extend([0x21, 0xff, 0x0b]);
+    bytes.extend(b"NETSCAPE2.0");
+    bytes.extend([3, 1, 0, 0, 0]);
+
+    for frame in frames {
+        bytes.extend([0x21, 0xf9, 4, 0]);
+        bytes.extend(delay.to_le_bytes());
+        bytes.extend([0, 0]);
+        bytes.push(0x2c);
+        bytes.extend([0, 0, 0, 0]);
+        bytes.extend(width.to_le_bytes());
+        bytes.extend(height.to_le_bytes());
+        bytes.push(0);
+        bytes.push(2);
+        for block in lzw(&frame.pixels, 2).chunks(255) {
+            bytes.push(block.len() as u8);
+            bytes.extend(block);
+        }
+        bytes.push(0);
+    }
+    bytes.push(0x3b);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    // GIF flavored LZW decoder, codes widening once the table fills the current width \\
+    fn unlzw(data: &[u8], min_size: u32) -> Vec<u8> {
+        let (clear, end) = (1usize << min_size, (1usize << min_size) + 1);
+        let bit = |pos: usize| (data[pos / 8] >> (pos % 8) & 1) as usize;
+        let (mut pos, mut size) = (0, min_size + 1);
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut previous: Option<usize> = None;
+        let mut pixels = Vec::new();
+        loop {
+            let code = (0..size as usize).fold(0, |code, i| code | bit(pos + i) << i);
+            pos += size as usize;
+            if code == clear {
+                table = (0..clear).map(|pixel| vec![pixel as u8]).chain([Vec::new(), Vec::new()]).collect();
+                size = min_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return pixels;
+            }
+            let entry = match previous {
+                None => table[code].clone(),
+                Some(previous) => {
+                    let entry = if code < table.len() {table[code].clone()} else {[table[previous].clone(), vec![table[previous][0]]].concat()};
+                    if table.len() < MAX_CODES as usize {
+                        table.push([table[previous].clone(), vec![entry[0]]].concat());
+                    }
+                    entry
+                }
+            };
+            pixels.extend(&entry);
+            previous = Some(code);
+            if table.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    // vectors checked against zlib's decompressor \\
+    #[test]
+    fn deflate_matches_known_streams() {
+        assert_eq!(hex(&deflate(b"", 1)), "7801030000000001");
+        assert_eq!(hex(&deflate(b"aaaaaaaaaaaaaaaaaaaa", 1)), "78014bc402004fa60795");
+        assert_eq!(hex(&deflate(b"abcabcabcabcabc", 1)), "78014b4c4a4e4c4a4e4c4a4e4c4a4e4c4a06002df505bf");
+        let rows = [0, 1, 1, 1, 1, 1, 1, 0].repeat(3);
+        assert_eq!(hex(&deflate(&rows, 8)), "78016360040306741a0000f90013");
+    }
+
+    #[test]
+    fn lzw_matches_known_streams() {
+        assert_eq!(hex(&lzw(&[1], 2)), "4c01");
+        assert_eq!(hex(&lzw(&[0, 0, 0, 1, 1, 1, 0, 0, 0, 1, 1, 1], 2)), "8483061805");
+    }
+
+    #[test]
+    fn lzw_round_trips_through_table_resets() {
+        let mut seed: u32 = 1;
+        let pixels: Vec<u8> = (0..100_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                (seed >> 16 & 3 == 0) as u8
+            })
+            .collect();
+        assert_eq!(unlzw(&lzw(&pixels, 2), 2), pixels);
+    }
+
+    #[test]
+    fn pbm_packs_rows_into_bytes() {
+        let raster = Raster::new(&[(0, 1), (9, 0)], (0, 0, 9, 1), 1);
+        assert_eq!(pbm(&raster), b"P4\n10 2\n\x80\x00\x00\x40".to_vec());
+    }
+
+    #[test]
+    fn png_uses_the_theme_background() {
+        let raster = Raster::new(&[(0, 0)], (0, 0, 0, 0), 1);
+        let bytes = png(&raster, &[[1, 2, 3], [4, 5, 6]]);
+        let palette = bytes.windows(4).position(|window| window == b"PLTE").unwrap() + 4;
+        assert_eq!(&bytes[palette..palette + 6], &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(&bytes[bytes.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+    }
+}
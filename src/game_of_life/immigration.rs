@@ -0,0 +1,451 @@
+use std::{io, collections::HashMap};
+use rand::Rng;
+use tui::{
+    backend::Backend,
+    widgets::canvas::Context,
+    style::{Color, Style},
+    text::Span,
+    Terminal
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use super::{Cursor, paint_population, rules::CONWAY, mode::{Mode, Controls, run_mode}};
+
+// Conway's rule where every live cell has a color. Survivors keep theirs,
+// and a newborn cell takes the color shared by most of its three parents;
+// when the three parents all differ (only possible in QuadLife) it takes the
+// fourth color. In a match two players take turns placing a few cells of
+// their color, then the board runs a few generations on its own.
+
+const HELP: &str = "[s]: 'paint', [tab]: 'next color', [1-4]: 'color', [v]: 'Immigration/QuadLife', [t]: 'start/stop match', [enter]: 'end turn', [p]: 'pause/resume', [n]: 'step', [c]: 'clear', [R]: 'random soup', [arrows]: 'move cursor', [shift+arrows]: 'pan', [i/o]: 'zoom in/out', [0]: 'fit', [+/-]: 'faster/slower', [q]: 'quit', [?]: 'all keys'";
+const USAGE: &str = "usage: game_collection immigration [--quad]";
+const COLORS: [(&str, Color); 4] = [
+    ("red", Color::Rgb(255, 80, 80)),
+    ("blue", Color::Rgb(80, 160, 255)),
+    ("green", Color::Rgb(120, 220, 80)),
+    ("yellow", Color::Rgb(240, 200, 60)),
+];
+const NEIGHBORS: [(i64, i64); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+const CELLS_PER_TURN: usize = 3;
+const GENERATIONS_PER_TURN: u32 = 10;
+const ROUNDS: u32 = 10;
+// each player starts with a random half of this square, mirrored across x = 0 \\
+const START_SIZE: i64 = 8;
+const SOUP_DENSITY: f64 = 0.4;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Variant {
+    Immigration,
+    QuadLife,
+}
+
+impl Variant {
+    fn name(self) -> &'static str {
+        match self {
+            Variant::Immigration => "Immigration",
+            Variant::QuadLife => "QuadLife",
+        }
+    }
+
+    fn colors(self) -> u8 {
+        match self {
+            Variant::Immigration => 2,
+            Variant::QuadLife => 4,
+        }
+    }
+
+    fn other(self) -> Variant {
+        match self {
+            Variant::Immigration => Variant::QuadLife,
+            Variant::QuadLife => Variant::Immigration,
+        }
+    }
+}
+
+
+// live cells by color, from 1 to the number of colors of the variant \\
+struct Board {
+    variant: Variant,
+    cells: HashMap<(i64, i64), u8>,
+    generation: u64,
+}
+
+impl Board {
+    fn new(variant: Variant) -> Board {
+        Board {variant, cells: HashMap::new(), generation: 0}
+    }
+
+    fn step(&mut self) {
+        // live neighbors around each cell, in total and by color \\
+        let mut around: HashMap<(i64, i64), [u8; 5]> = HashMap::new();
+        for (&(x, y), &color) in &self.cells {
+            for (dx, dy) in NEIGHBORS {
+                let tally = around.entry((x + dx, y + dy)).or_insert([0; 5]);
+                tally[0] += 1;
+                tally[color as usize] += 1;
+            }
+        }
+        self.cells = around.into_iter()
+            .filter(|(cell, tally)| CONWAY.next_state(self.cells.contains_key(cell), tally[0] as u32))
+            .map(|(cell, tally)| (cell, self.cells.get(&cell).copied().unwrap_or_else(|| newborn(&tally))))
+            .collect();
+        self.generation += 1;
+    }
+
+    fn count(&self, color: u8) -> usize {
+        self.cells.values().filter(|&&c| c == color).count()
+    }
+
+    fn cells_in(&self, color: u8, (x0, y0, x1, y1): (i64, i64, i64, i64)) -> Vec<(i64, i64)> {
+        self.cells.iter()
+            .filter(|(&(x, y), &c)| c == color && x >= x0 && y >= y0 && x <= x1 && y <= y1)
+            .map(|(&cell, _)| cell)
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        self.cells.keys().fold(None, |bounds, &(x, y)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y))),
+        })
+    }
+
+    // a random square for the first color, mirrored for the second \\
+    fn mirrored_soup(&mut self) {
+        let mut rng = rand::thread_rng();
+        for y in -START_SIZE / 2..START_SIZE / 2 {
+            for x in 2..2 + START_SIZE {
+                if rng.gen_bool(SOUP_DENSITY) {
+                    self.cells.insert((-x, y), 1);
+                    self.cells.insert((x - 1, y), 2);
+                }
+            }
+        }
+    }
+
+    // a random square of every color of the variant around the cursor \\
+    fn random_soup(&mut self, cx: i64, cy: i64) {
+        let mut rng = rand::thread_rng();
+        let size = 2 * START_SIZE;
+        for y in cy - size / 2..cy + size / 2 {
+            for x in cx - size / 2..cx + size / 2 {
+                if rng.gen_bool(SOUP_DENSITY) {
+                    self.cells.insert((x, y), rng.gen_range(1..=self.variant.colors()));
+                }
+            }
+        }
+    }
+
+    fn switch_variant(&mut self) {
+        self.variant = self.variant.other();
+        let colors = self.variant.colors();
+        for color in self.cells.values_mut() {
+            *color = (*color - 1) % colors + 1;
+        }
+    }
+}
+
+// color shared by most parents, or the one none of them has \\
+fn newborn(tally: &[u8; 5]) -> u8 {
+    match (1..=4).find(|&color| tally[color] >= 2) {
+        Some(color) => color as u8,
+        None => (1..=4).find(|&color| tally[color] == 0).unwrap() as u8,
+    }
+}
+
+
+// two players placing cells in turn, player 1 in the first color \\
+struct Match {
+    player: u8,
+    round: u32,
+    placed: Vec<(i64, i64)>,
+    // generations left to run before the next turn \\
+    evolving: u32,
+}
+
+impl Match {
+    fn new() -> Match {
+        Match {player: 1, round: 1, placed: Vec::new(), evolving: 0}
+    }
+
+    fn over(&self) -> bool {
+        self.round > ROUNDS
+    }
+
+    // places a cell of the player's color, or takes back one placed this turn \\
+    fn place(&mut self, board: &mut Board, x: i64, y: i64) -> Option<String> {
+        if self.over() {
+            return Some(String::from("the match is over"));
+        }
+        if self.evolving > 0 {
+            return Some(String::from("wait for the board to settle"));
+        }
+        if let Some(idx) = self.placed.iter().position(|&cell| cell == (x, y)) {
+            self.placed.remove(idx);
+            board.cells.remove(&(x, y));
+            return None;
+        }
+        if board.cells.contains_key(&(x, y)) {
+            return Some(String::from("cell already taken"));
+        }
+        if self.placed.len() >= CELLS_PER_TURN {
+            return Some(format!("only {} cells per turn, press enter", CELLS_PER_TURN));
+        }
+        board.cells.insert((x, y), self.player);
+        self.placed.push((x, y));
+        None
+    }
+
+    fn end_turn(&mut self) {
+        if self.evolving == 0 && !self.over() {
+            self.placed.clear();
+            self.evolving = GENERATIONS_PER_TURN;
+        }
+    }
+
+    // one generation of the board between turns, then the next player's turn \\
+    fn evolve(&mut self, board: &mut Board) {
+        board.step();
+        self.evolving -= 1;
+        if self.evolving == 0 {
+            if self.player == 2 {
+                self.round += 1;
+            }
+            self.player = 3 - self.player;
+        }
+    }
+
+    fn describe(&self, board: &Board) -> String {
+        if self.over() {
+            return match board.count(1).cmp(&board.count(2)) {
+                std::cmp::Ordering::Greater => format!("match over: {} wins", COLORS[0].0),
+                std::cmp::Ordering::Less => format!("match over: {} wins", COLORS[1].0),
+                std::cmp::Ordering::Equal => String::from("match over: draw"),
+            };
+        }
+        if self.evolving > 0 {
+            return format!("round {}/{}: evolving, {} generations left", self.round, ROUNDS, self.evolving);
+        }
+        format!(
+            "round {}/{}: {} to play, {} cells left",
+            self.round, ROUNDS, COLORS[self.player as usize - 1].0, CELLS_PER_TURN - self.placed.len(),
+        )
+    }
+}
+
+
+pub fn run_immigration<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+    run_board(terminal, Variant::Immigration)
+}
+
+pub fn run_immigration_cli(args: &[String]) -> io::Result<()> {
+    let variant = match args {
+        [] => Variant::Immigration,
+        [flag] if flag == "--quad" => Variant::QuadLife,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, USAGE)),
+    };
+    crate::with_terminal(|terminal| run_board(terminal, variant))
+}
+
+fn run_board<B: Backend>(terminal: &mut Terminal<B>, variant: Variant) -> io::Result<()> {
+    let controls = Controls::new(terminal.size()?, Some(Cursor {x: 0, y: 0}));
+    run_mode(terminal, &mut Immigration {board: Board::new(variant), brush: 1, game: None}, controls)
+}
+
+struct Immigration {
+    board: Board,
+    brush: u8,
+    game: Option<Match>,
+}
+
+impl Mode for Immigration {
+    fn name(&self) -> String {
+        String::from(self.board.variant.name())
+    }
+
+    fn help(&self) -> &'static str {
+        HELP
+    }
+
+    fn state(&self) -> Option<Span<'static>> {
+        self.game.as_ref().map(|game| Span::styled(game.describe(&self.board), Style::default().fg(Color::Magenta)))
+    }
+
+    fn stats(&self) -> Vec<Span<'static>> {
+        let board = &self.board;
+        let mut stats = vec![Span::raw(format!("Generation: {} | Score:", board.generation))];
+        for color in 1..=board.variant.colors() {
+            let (name, rgb) = COLORS[color as usize - 1];
+            stats.push(Span::styled(format!(" {} {}", name, board.count(color)), Style::default().fg(rgb)));
+        }
+        if self.game.is_none() {
+            let (name, rgb) = COLORS[self.brush as usize - 1];
+            stats.push(Span::raw(" | Brush: "));
+            stats.push(Span::styled(format!("█ {}", name), Style::default().fg(rgb)));
+        }
+        stats
+    }
+
+    fn paint(&self, ctx: &mut Context, controls: &Controls) {
+        let view = &controls.view;
+        let (w, h) = view.span();
+        let visible = (view.x, view.y, view.x + w - 1, view.y + h - 1);
+        for color in 1..=self.board.variant.colors() {
+            paint_population(ctx, view, &self.board.cells_in(color, visible), COLORS[color as usize - 1].1);
+        }
+    }
+
+    fn step(&mut self) {
+        self.board.step();
+    }
+
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        self.board.bounding_box()
+    }
+
+    fn advance(&mut self, controls: &Controls) {
+        match &mut self.game {
+            Some(game) if game.evolving > 0 => game.evolve(&mut self.board),
+            Some(_) => {}
+            None if !controls.paused => controls.turbo(|| self.board.step()),
+            None => {}
+        }
+    }
+
+    fn key(&mut self, key: KeyEvent, controls: &mut Controls) -> io::Result<bool> {
+        let (x, y) = controls.cursor.as_ref().map_or((0, 0), |cursor| (cursor.x, cursor.y));
+        let board = &mut self.board;
+        match key.code {
+            KeyCode::Char('t') => {
+                self.game = match self.game {
+                    Some(_) => None,
+                    None => {
+                        *board = Board::new(board.variant);
+                        board.mirrored_soup();
+                        controls.cursor = Some(Cursor {x: 0, y: 0});
+                        controls.view.center_on(0, 0);
+                        controls.paused = true;
+                        Some(Match::new())
+                    }
+                };
+            }
+            KeyCode::Char('s') => match &mut self.game {
+                Some(game) => controls.status = game.place(board, x, y),
+                None => match board.cells.get(&(x, y)) {
+                    Some(&color) if color == self.brush => {
+                        board.cells.remove(&(x, y));
+                    }
+                    _ => {
+                        board.cells.insert((x, y), self.brush);
+                    }
+                },
+            },
+            KeyCode::Enter => if let Some(game) = &mut self.game {
+                game.end_turn();
+            }
+            // the rest changes the board outside of a match \\
+            _ if self.game.is_some() && matches!(key.code, KeyCode::Char('p' | 'n' | 'c' | 'R' | 'v' | '1'..='4') | KeyCode::Tab) =>
+                controls.status = Some(String::from("stop the match first ([t])")),
+            KeyCode::Char('c') => {
+                *board = Board::new(board.variant);
+                controls.paused = true;
+            }
+            KeyCode::Char('R') => board.random_soup(x, y),
+            KeyCode::Char('v') => {
+                board.switch_variant();
+                self.brush = (self.brush - 1) % board.variant.colors() + 1;
+                controls.status = Some(format!("variant: {}", board.variant.name()));
+            }
+            KeyCode::Tab => self.brush = self.brush % board.variant.colors() + 1,
+            KeyCode::Char(c @ '1'..='4') if (c as u8 - b'0') <= board.variant.colors() => self.brush = c as u8 - b'0',
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(variant: Variant, cells: &[((i64, i64), u8)]) -> Board {
+        let mut board = Board::new(variant);
+        board.cells.extend(cells.iter().copied());
+        board
+    }
+
+    #[test]
+    fn newborns_take_the_majority_color() {
+        assert_eq!(newborn(&[3, 2, 1, 0, 0]), 1);
+        assert_eq!(newborn(&[3, 1, 2, 0, 0]), 2);
+        assert_eq!(newborn(&[3, 0, 1, 2, 0]), 3);
+    }
+
+    #[test]
+    fn quadlife_newborns_of_three_colors_take_the_fourth() {
+        assert_eq!(newborn(&[3, 1, 1, 1, 0]), 4);
+        assert_eq!(newborn(&[3, 0, 1, 1, 1]), 1);
+        assert_eq!(newborn(&[3, 1, 0, 1, 1]), 2);
+    }
+
+    #[test]
+    fn survivors_keep_their_color() {
+        // a blinker of two reds and a blue: the ends die, two reds are born \\
+        let mut board = board(Variant::Immigration, &[((-1, 0), 1), ((0, 0), 2), ((1, 0), 1)]);
+        board.step();
+        let mut cells: Vec<((i64, i64), u8)> = board.cells.into_iter().collect();
+        cells.sort();
+        assert_eq!(cells, vec![((0, -1), 1), ((0, 0), 2), ((0, 1), 1)]);
+    }
+
+    #[test]
+    fn switching_variants_folds_the_colors() {
+        let mut board = board(Variant::QuadLife, &[((0, 0), 3), ((1, 0), 4)]);
+        board.switch_variant();
+        assert!(board.variant == Variant::Immigration);
+        assert_eq!((board.count(1), board.count(2)), (1, 1));
+    }
+
+    #[test]
+    fn players_take_turns_placing_cells() {
+        let mut board = board(Variant::Immigration, &[((5, 5), 2)]);
+        let mut game = Match::new();
+        assert!(game.place(&mut board, 5, 5).is_some());
+        for x in 0..CELLS_PER_TURN as i64 {
+            assert!(game.place(&mut board, x, 0).is_none());
+        }
+        assert!(game.place(&mut board, 9, 9).is_some());
+        // taking a cell back frees a place for another \\
+        assert!(game.place(&mut board, 0, 0).is_none());
+        assert!(!board.cells.contains_key(&(0, 0)));
+        assert!(game.place(&mut board, 9, 9).is_none());
+        assert_eq!(board.count(1), CELLS_PER_TURN);
+
+        game.end_turn();
+        assert_eq!(game.evolving, GENERATIONS_PER_TURN);
+        assert!(game.place(&mut board, 7, 7).is_some());
+        while game.evolving > 0 {
+            game.evolve(&mut board);
+        }
+        assert_eq!(board.generation, GENERATIONS_PER_TURN as u64);
+        assert_eq!((game.player, game.round), (2, 1));
+        assert!(game.place(&mut board, 7, 7).is_none());
+        assert!(board.cells.get(&(7, 7)) == Some(&2));
+    }
+
+    #[test]
+    fn the_match_ends_after_the_last_round() {
+        let mut board = board(Variant::Immigration, &[((0, 0), 1), ((0, 1), 1), ((1, 0), 1), ((1, 1), 1)]);
+        let mut game = Match::new();
+        for _ in 0..2 * ROUNDS {
+            assert!(!game.over());
+            game.end_turn();
+            while game.evolving > 0 {
+                game.evolve(&mut board);
+            }
+        }
+        assert!(game.over());
+        assert!(game.place(&mut board, 5, 5).is_some());
+        assert_eq!(game.describe(&board), "match over: red wins");
+    }
+}
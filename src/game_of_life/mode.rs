@@ -0,0 +1,220 @@
+use std::{io, time::{Duration, Instant}};
+use tui::{
+    backend::Backend,
+    widgets::{Block, Borders, Paragraph, canvas::{Canvas, Context}},
+    layout::{Layout, Constraint, Rect},
+    style::{Color, Style},
+    text::Span,
+    symbols,
+    Frame,
+    Terminal
+};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use super::{Cursor, SPEEDS, DEFAULT_SPEED, TURBO_BUDGET, PROMPT_HELP, step_interval, describe_speed, help_line, help_ui, paint_cell, view::Viewport, prompt::Prompt};
+
+// The side modes (turmites, elementary automata, Immigration and Wireworld)
+// share one screen: a help bar over a canvas, with the viewport, cursor,
+// pause and speed controls in common. A mode steps and paints its own world
+// and sees every key first; the keys it passes on get the shared bindings.
+
+pub struct Controls {
+    pub view: Viewport,
+    // none where there is nothing to point at, arrows pan instead \\
+    pub cursor: Option<Cursor>,
+    pub paused: bool,
+    pub speed: usize,
+    pub prompt: Option<Prompt>,
+    pub status: Option<String>,
+    pub show_help: bool,
+}
+
+impl Controls {
+    pub fn new(area: Rect, cursor: Option<Cursor>) -> Controls {
+        Controls {
+            view: Viewport::new(area.width as i64 - 2, area.height as i64 - 5),
+            cursor,
+            paused: true,
+            speed: DEFAULT_SPEED,
+            prompt: None,
+            status: None,
+            show_help: false,
+        }
+    }
+
+    // steps once, or as often as the frame allows in turbo \\
+    pub fn turbo(&self, mut step: impl FnMut()) {
+        let frame_start = Instant::now();
+        loop {
+            step();
+            if SPEEDS.get(self.speed).is_some() || frame_start.elapsed() >= TURBO_BUDGET {
+                break;
+            }
+        }
+    }
+
+    // where zooming centers the view \\
+    fn focus(&self) -> (i64, i64) {
+        self.cursor.as_ref().map_or((0, 0), |cursor| (cursor.x, cursor.y))
+    }
+}
+
+pub trait Mode {
+    fn name(&self) -> String;
+    fn help(&self) -> &'static str;
+    // the title between the pause state and the speed \\
+    fn stats(&self) -> Vec<Span<'static>>;
+    fn paint(&self, ctx: &mut Context, controls: &Controls);
+    fn step(&mut self);
+    // handles a key of the mode, false passes it on to the shared bindings \\
+    fn key(&mut self, key: KeyEvent, controls: &mut Controls) -> io::Result<bool>;
+
+    // what [0] fits the view to \\
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        None
+    }
+
+    // takes the text typed at the prompt, or says what is wrong with it \\
+    fn answer(&mut self, _prompt: &Prompt) -> Result<(), String> {
+        Ok(())
+    }
+
+    // shown instead of paused/playing \\
+    fn state(&self) -> Option<Span<'static>> {
+        None
+    }
+
+    // highlighted after the title \\
+    fn notes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // runs whenever a step is due \\
+    fn advance(&mut self, controls: &Controls) {
+        if !controls.paused {
+            controls.turbo(|| self.step());
+        }
+    }
+
+    // moves the view before each frame \\
+    fn frame(&mut self, _view: &mut Viewport) {}
+}
+
+pub fn run_mode<B: Backend, M: Mode>(terminal: &mut Terminal<B>, mode: &mut M, mut controls: Controls) -> io::Result<()> {
+    let mut last_tick = Instant::now();
+    let tick_rate = Duration::from_millis(50);
+
+    loop {
+        let area = terminal.size()?;
+        controls.view.resize(area.width as i64 - 2, area.height as i64 - 5);
+        mode.frame(&mut controls.view);
+        terminal.draw(|f| ui(f, mode, &controls))?;
+
+        let timeout = step_interval(controls.speed)
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0))
+            .min(tick_rate);
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                controls.status = None;
+                if let Some(prompt) = &mut controls.prompt {
+                    match key.code {
+                        KeyCode::Char(c) if prompt.accepts(c) => prompt.text.push(c),
+                        KeyCode::Backspace => {
+                            prompt.text.pop();
+                        }
+                        KeyCode::Enter => match mode.answer(prompt) {
+                            Ok(()) => controls.prompt = None,
+                            Err(err) => controls.status = Some(err),
+                        },
+                        KeyCode::Esc => controls.prompt = None,
+                        _ => {}
+                    }
+                    continue;
+                }
+                if !mode.key(key, &mut controls)? {
+                    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+                    let (x, y) = controls.focus();
+                    match key.code {
+                        KeyCode::Char('q') => return Ok(()),
+                        KeyCode::Char('p') => controls.paused = !controls.paused,
+                        KeyCode::Char('?') => controls.show_help = !controls.show_help,
+                        KeyCode::Char('n') if controls.paused => mode.step(),
+                        KeyCode::Char('i') => controls.view.zoom(1, x, y),
+                        KeyCode::Char('o') => controls.view.zoom(-1, x, y),
+                        KeyCode::Char('0') => if let Some(bounding_box) = mode.bounding_box() {
+                            controls.view.fit(bounding_box);
+                        }
+                        KeyCode::Char('+') => controls.speed = (controls.speed + 1).min(SPEEDS.len()),
+                        KeyCode::Char('-') => controls.speed = controls.speed.saturating_sub(1),
+                        code => if let Some((dx, dy)) = arrow(code) {
+                            match &mut controls.cursor {
+                                Some(cursor) if shift => cursor.pan(&mut controls.view, dx, dy),
+                                Some(cursor) => cursor.shift(&mut controls.view, dx, dy),
+                                None => controls.view.pan(dx, dy),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if last_tick.elapsed() >= step_interval(controls.speed) {
+            mode.advance(&controls);
+            last_tick = Instant::now();
+        }
+    }
+}
+
+pub fn arrow(code: KeyCode) -> Option<(i64, i64)> {
+    match code {
+        KeyCode::Up => Some((0, 1)),
+        KeyCode::Down => Some((0, -1)),
+        KeyCode::Left => Some((-1, 0)),
+        KeyCode::Right => Some((1, 0)),
+        _ => None,
+    }
+}
+
+fn ui<B: Backend, M: Mode>(f: &mut Frame<B>, mode: &M, controls: &Controls) {
+    let chunks = Layout::default()
+        .constraints([Constraint::Length(3), Constraint::Min(2)].as_ref())
+        .split(f.size());
+
+    let help = match &controls.prompt {
+        Some(prompt) => format!("{} {}_    {}", prompt.question(), prompt.text, PROMPT_HELP),
+        None => help_line(mode.help(), chunks[0].width.saturating_sub(2) as usize),
+    };
+    let paragraph = Paragraph::new(help)
+        .block(Block::default().title("[Help]").borders(Borders::ALL));
+    f.render_widget(paragraph, chunks[0]);
+
+    let state = mode.state().unwrap_or_else(|| {
+        if controls.paused {Span::styled("paused", Style::default().fg(Color::Red))}
+        else {Span::styled("playing", Style::default().fg(Color::Green))}
+    });
+    let mut title = vec![Span::raw(format!("[{}: ", mode.name())), state, Span::raw("| ")];
+    title.extend(mode.stats());
+    title.push(Span::raw(format!(" | Speed: {}]", describe_speed(controls.speed, 0.0))));
+    for note in mode.notes() {
+        title.push(Span::styled(format!("[{}]", note), Style::default().fg(Color::Magenta)));
+    }
+    if let Some(status) = &controls.status {
+        title.push(Span::styled(format!("[{}]", status), Style::default().fg(Color::Yellow)));
+    }
+    let view = &controls.view;
+    let canvas = Canvas::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .x_bounds([0.0, (view.cols - 1) as f64])
+        .y_bounds([0.0, (view.rows - 1) as f64])
+        .marker(symbols::Marker::Block)
+        .paint(|ctx| {
+            mode.paint(ctx, controls);
+            if let Some(cursor) = &controls.cursor {
+                paint_cell(ctx, view, cursor.x, cursor.y, "█", Color::White);
+            }
+        });
+    f.render_widget(canvas, chunks[1]);
+    if controls.show_help {
+        help_ui(f, mode.help(), chunks[1]);
+    }
+}
@@ -0,0 +1,74 @@
+use super::{Population, formats::{self, Format}};
+
+pub const CATEGORIES: [&str; 4] = [
+    "Spaceships",
+    "Oscillators",
+    "Guns",
+    "Methuselahs",
+];
+
+// (category, name, rle) \\
+pub const PATTERNS: [(&str, &str, &str); 7] = [
+    ("Spaceships", "Glider", "bo$2bo$3o!"),
+    ("Spaceships", "LWSS", "bo2bo$o4b$o3bo$4o!"),
+    ("Oscillators", "Pulsar", "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!"),
+    ("Guns", "Gosper glider gun", "24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!"),
+    ("Methuselahs", "R-pentomino", "b2o$2o$bo!"),
+    ("Methuselahs", "Acorn", "bo$3bo$2o2b3o!"),
+    ("Methuselahs", "Diehard", "6bo$2o$bo3b3o!"),
+];
+
+
+pub struct Library {
+    pub selected: usize,
+    pub stamp: Population,
+}
+
+impl Library {
+    pub fn new() -> Library {
+        Library {
+            selected: 0,
+            stamp: load(0),
+        }
+    }
+
+    // patterns in display order, grouped by category \\
+    pub fn ordered() -> Vec<usize> {
+        CATEGORIES.iter()
+            .flat_map(|category| (0..PATTERNS.len()).filter(move |i| PATTERNS[*i].0 == *category))
+            .collect()
+    }
+
+    fn select(&mut self, offset: isize) {
+        let order = Library::ordered();
+        let position = order.iter().position(|i| *i == self.selected).unwrap_or(0) as isize;
+        let position = (position + offset).rem_euclid(order.len() as isize) as usize;
+        self.selected = order[position];
+        self.stamp = load(self.selected);
+    }
+
+    pub fn next(&mut self) {
+        self.select(1)
+    }
+
+    pub fn previous(&mut self) {
+        self.select(-1)
+    }
+
+    pub fn rotate(&mut self) {
+        self.stamp = self.stamp.rotated();
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        self.stamp = self.stamp.flipped_horizontal();
+    }
+
+    pub fn flip_vertical(&mut self) {
+        self.stamp = self.stamp.flipped_vertical();
+    }
+}
+
+
+fn load(idx: usize) -> Population {
+    formats::read(Format::Rle, PATTERNS[idx].2).expect("built-in patterns are valid RLE")
+}
@@ -0,0 +1,89 @@
+use super::breakpoints::Breakpoint;
+
+// A line typed in the help bar: numbers for run targets, breakpoints,
+// elementary rules, board sizes and soup seeds, free text for snapshot
+// names, turmite rules and image files.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ask {
+    RunFor,
+    RunUntil,
+    PopulationBelow,
+    SnapshotName,
+    TurmiteRule,
+    RuleNumber,
+    BoardSize,
+    SoupSeed,
+    ImageFile,
+}
+
+pub struct Prompt {
+    pub ask: Ask,
+    pub text: String,
+}
+
+impl Prompt {
+    pub fn new(ask: Ask) -> Prompt {
+        Prompt {ask, text: String::new()}
+    }
+
+    pub fn question(&self) -> &'static str {
+        match self.ask {
+            Ask::RunFor => "run for how many generations?",
+            Ask::RunUntil => "run until which generation?",
+            Ask::PopulationBelow => "pause when the population drops below?",
+            Ask::SnapshotName => "snapshot name:",
+            Ask::RuleNumber => "rule number (0-255):",
+            Ask::BoardSize => "board size (e.g. 200x100, empty for unbounded):",
+            Ask::SoupSeed => "soup seed:",
+            Ask::ImageFile => "image file (.png or .pbm) and cell size, empty for life.png 8:",
+            Ask::TurmiteRule => "turmite rule (RL, LLRR or {{{1, 2, 0}, {0, 8, 0}}}):",
+        }
+    }
+
+    pub fn accepts(&self, c: char) -> bool {
+        match self.ask {
+            Ask::SnapshotName | Ask::TurmiteRule | Ask::ImageFile => !c.is_control(),
+            Ask::BoardSize => c.is_ascii_digit() || c == 'x',
+            _ => c.is_ascii_digit(),
+        }
+    }
+
+    // the breakpoint asked for, from the current generation \\
+    pub fn breakpoint(&self, generation: u64) -> Result<Breakpoint, String> {
+        let number: u64 = self.text.parse().map_err(|_| format!("'{}' is not a number", self.text))?;
+        match self.ask {
+            Ask::RunFor if number > 0 => Ok(Breakpoint::Generation(generation + number)),
+            Ask::RunFor => Err(String::from("nothing to run")),
+            Ask::RunUntil if number > generation => Ok(Breakpoint::Generation(number)),
+            Ask::RunUntil => Err(format!("generation {} is already past", number)),
+            Ask::PopulationBelow => Ok(Breakpoint::PopulationBelow(number)),
+            Ask::SnapshotName | Ask::TurmiteRule | Ask::RuleNumber | Ask::BoardSize | Ask::SoupSeed | Ask::ImageFile =>
+                Err(String::from("not a breakpoint")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(ask: Ask, text: &str, generation: u64) -> Result<Breakpoint, String> {
+        Prompt {ask, text: String::from(text)}.breakpoint(generation)
+    }
+
+    #[test]
+    fn breakpoints_count_from_the_current_generation() {
+        assert!(answer(Ask::RunFor, "10", 5) == Ok(Breakpoint::Generation(15)));
+        assert!(answer(Ask::RunUntil, "10", 5) == Ok(Breakpoint::Generation(10)));
+        assert!(answer(Ask::PopulationBelow, "3", 5) == Ok(Breakpoint::PopulationBelow(3)));
+    }
+
+    #[test]
+    fn rejects_empty_past_and_unrelated_answers() {
+        assert_eq!(answer(Ask::RunFor, "0", 5).err(), Some(String::from("nothing to run")));
+        assert_eq!(answer(Ask::RunUntil, "5", 5).err(), Some(String::from("generation 5 is already past")));
+        assert_eq!(answer(Ask::RunFor, "", 5).err(), Some(String::from("'' is not a number")));
+        assert_eq!(answer(Ask::SnapshotName, "12", 5).err(), Some(String::from("not a breakpoint")));
+    }
+}
@@ -0,0 +1,112 @@
+use std::{fmt, io, str::FromStr};
+
+// Life-like rules in B/S notation: bit n of `birth` makes a dead cell with
+// n live neighbors come alive, bit n of `survival` keeps a live one alive.
+// B0 is refused since it would light up the whole unbounded plane.
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+    pub birth: u16,
+    pub survival: u16,
+}
+
+pub const CONWAY: Rule = Rule {birth: 1 << 3, survival: 1 << 2 | 1 << 3};
+
+impl Rule {
+    pub fn is_conway(&self) -> bool {
+        *self == CONWAY
+    }
+
+    pub fn next_state(&self, alive: bool, live_neighbors: u32) -> bool {
+        let mask = if alive {self.survival} else {self.birth};
+        mask >> live_neighbors & 1 == 1
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        CONWAY
+    }
+}
+
+fn digits(text: &str) -> Option<u16> {
+    text.chars().try_fold(0u16, |mask, c| match c.to_digit(10) {
+        Some(n) if n <= 8 => Some(mask | 1 << n),
+        _ => None,
+    })
+}
+
+// accepts "B3/S23" as well as the older "23/3" (survival/birth) and "Life";
+// a bounded grid suffix such as ":T100,80" is ignored \\
+impl FromStr for Rule {
+    type Err = io::Error;
+
+    fn from_str(text: &str) -> io::Result<Rule> {
+        let text = text.split_once(':').map_or(text, |(rule, _)| rule).trim();
+        if text.eq_ignore_ascii_case("life") {
+            return Ok(CONWAY);
+        }
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid rule '{}'", text));
+        let (first, second) = text.split_once('/').ok_or_else(invalid)?;
+        let (birth, survival) = match (first.chars().next(), second.chars().next()) {
+            (Some('B' | 'b'), Some('S' | 's')) => (&first[1..], &second[1..]),
+            (Some('S' | 's'), Some('B' | 'b')) => (&second[1..], &first[1..]),
+            _ => (second, first),
+        };
+        let rule = Rule {
+            birth: digits(birth).ok_or_else(invalid)?,
+            survival: digits(survival).ok_or_else(invalid)?,
+        };
+        if rule.birth & 1 == 1 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "B0 rules are not supported"));
+        }
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |mask: u16| (0..=8).filter(|n| mask >> n & 1 == 1).map(|n| n.to_string()).collect::<String>();
+        write!(f, "B{}/S{}", list(self.birth), list(self.survival))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_notation() {
+        let highlife = Rule {birth: 1 << 3 | 1 << 6, survival: 1 << 2 | 1 << 3};
+        for text in ["B36/S23", "b36/s23", "S23/B36", "23/36", " B36/S23 ", "B36/S23:T100,80", "B36/S23:P64,64"] {
+            assert!(text.parse::<Rule>().unwrap() == highlife, "{}", text);
+        }
+        assert!("Life".parse::<Rule>().unwrap() == CONWAY);
+        assert!("life:T20,20".parse::<Rule>().unwrap() == CONWAY);
+        assert!("B/S".parse::<Rule>().unwrap() == Rule {birth: 0, survival: 0});
+    }
+
+    #[test]
+    fn displays_in_b_s_notation() {
+        for text in ["B3/S23", "B36/S23", "B2/S", "B3678/S34678"] {
+            assert_eq!(text.parse::<Rule>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        for text in ["", "B3", "B9/S23", "B3/S2x", "Highlife", "B03/S23"] {
+            assert!(text.parse::<Rule>().is_err(), "{}", text);
+        }
+        let err = "  B3/Q23  ".parse::<Rule>().err().unwrap();
+        assert_eq!(err.to_string(), "invalid rule 'B3/Q23'");
+    }
+
+    #[test]
+    fn follows_the_rule_table() {
+        assert!(CONWAY.next_state(false, 3));
+        assert!(!CONWAY.next_state(false, 2));
+        assert!(CONWAY.next_state(true, 2));
+        assert!(!CONWAY.next_state(true, 4));
+    }
+}
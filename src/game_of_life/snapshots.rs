@@ -0,0 +1,167 @@
+use std::{fs, io, path::Path};
+use super::{Population, parse_board_size, formats::{self, Format}};
+
+// A saved game is a sequence of RLE patterns: the board first, then one per
+// snapshot, each under its `#N` name. Golly's `#CXRLE` line keeps where the
+// pattern sits (top left corner, with y growing downward) and its
+// generation, so the file still opens as the board in other programs.
+
+const BOARD: &str = "board";
+
+
+pub struct Snapshot {
+    pub name: String,
+    pub population: Population,
+}
+
+pub struct Snapshots {
+    pub list: Vec<Snapshot>,
+    pub selected: usize,
+}
+
+impl Snapshots {
+    pub fn new() -> Snapshots {
+        Snapshots {list: Vec::new(), selected: 0}
+    }
+
+    // keeps a copy of the population, named after its generation when no name is given \\
+    pub fn take(&mut self, name: &str, population: &Population) -> &Snapshot {
+        let name = match name.trim() {
+            "" => format!("generation {}", population.generation),
+            name => String::from(name),
+        };
+        self.list.push(Snapshot {name, population: population.clone()});
+        self.selected = self.list.len() - 1;
+        &self.list[self.selected]
+    }
+
+    pub fn selected(&self) -> Option<&Snapshot> {
+        self.list.get(self.selected)
+    }
+
+    pub fn select(&mut self, offset: isize) {
+        if !self.list.is_empty() {
+            self.selected = (self.selected as isize + offset).rem_euclid(self.list.len() as isize) as usize;
+        }
+    }
+
+    pub fn remove(&mut self) -> Option<Snapshot> {
+        if self.selected >= self.list.len() {
+            return None;
+        }
+        let snapshot = self.list.remove(self.selected);
+        self.selected = self.selected.min(self.list.len().saturating_sub(1));
+        Some(snapshot)
+    }
+}
+
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_section(text: &mut String, name: &str, population: &Population) {
+    text.push_str(&format!("#N {}\n", name));
+    let position = population.bounding_box().map_or(String::new(), |(x0, _, _, y1)| format!(" Pos={},{}", x0, -y1));
+    text.push_str(&format!("#CXRLE{} Gen={}\n", position, population.generation));
+    if let Some((cols, rows)) = population.size {
+        text.push_str(&format!("#C board {}x{}\n", cols, rows));
+    }
+    text.push_str(&formats::write(Format::Rle, population));
+}
+
+// one pattern of a saved game, put back where it was \\
+fn read_section(section: &str) -> io::Result<Snapshot> {
+    let mut name = String::from(BOARD);
+    // sections written without a position start at the origin \\
+    let (mut position, mut generation, mut size) = ((0, 0), 0, None);
+    for line in section.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("#N") {
+            name = String::from(value.trim());
+        } else if let Some(fields) = line.strip_prefix("#CXRLE") {
+            for (key, value) in fields.split_whitespace().filter_map(|field| field.split_once('=')) {
+                match key {
+                    "Pos" => position = value.split_once(',')
+                        .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+                        .ok_or_else(|| invalid(format!("invalid position '{}'", value)))?,
+                    "Gen" => generation = value.parse().map_err(|_| invalid(format!("invalid generation '{}'", value)))?,
+                    _ => {}
+                }
+            }
+        } else if let Some(dimensions) = line.strip_prefix("#C board ") {
+            size = parse_board_size(dimensions);
+        }
+    }
+
+    let pattern = formats::read(Format::Rle, section)?;
+    let mut population = match size {
+        Some((cols, rows)) => Population::with_size(cols, rows),
+        None => Population::new(),
+    };
+    let ((x, y), (_, rows)) = (position, pattern.dimensions());
+    population.stamp(&pattern, x, -y - rows + 1);
+    population.rule = pattern.rule;
+    population.generation = generation;
+    Ok(Snapshot {name, population})
+}
+
+pub fn save(path: &Path, board: &Population, snapshots: &Snapshots) -> io::Result<()> {
+    let mut text = String::new();
+    write_section(&mut text, BOARD, board);
+    for snapshot in &snapshots.list {
+        write_section(&mut text, &snapshot.name, &snapshot.population);
+    }
+    fs::write(path, text)
+}
+
+// the board and the snapshots of a saved game \\
+pub fn load(path: &Path) -> io::Result<(Population, Snapshots)> {
+    let text = fs::read_to_string(path)?;
+    let mut sections: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with("#N") || sections.is_empty() {
+            sections.push(String::new());
+        }
+        let section = sections.last_mut().unwrap();
+        section.push_str(line);
+        section.push('\n');
+    }
+    let mut states = sections.iter().map(|section| read_section(section)).collect::<io::Result<Vec<Snapshot>>>()?.into_iter();
+    let board = states.next().ok_or_else(|| invalid(format!("empty saved game: {}", path.display())))?.population;
+    Ok((board, Snapshots {list: states.collect(), selected: 0}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(population: &Population) -> Vec<(i64, i64)> {
+        let mut cells = population.live_cells();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn sections_come_back_where_they_were() {
+        let mut population = Population::with_size(40, 30);
+        for (x, y) in [(12, 7), (13, 7), (14, 8), (12, 9)] {
+            population.set(x, y, true);
+        }
+        population.generation = 17;
+        let mut text = String::new();
+        write_section(&mut text, "glider", &population);
+        let snapshot = read_section(&text).unwrap();
+        assert_eq!(snapshot.name, "glider");
+        assert_eq!(snapshot.population.generation, 17);
+        assert_eq!(snapshot.population.size, Some((40, 30)));
+        assert_eq!(sorted(&snapshot.population), sorted(&population));
+    }
+
+    #[test]
+    fn sections_without_a_position_start_at_the_origin() {
+        let snapshot = read_section("#N plain\nx = 2, y = 2\n2o$bo!\n").unwrap();
+        // top left corner at the origin, y growing upward \\
+        assert_eq!(sorted(&snapshot.population), vec![(0, 0), (1, -1), (1, 0)]);
+        assert!(read_section("#CXRLE Pos=3 Gen=0\nx = 1, y = 1\no!\n").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use super::Population;
+
+// A soup is fully described by its density, symmetry and seed: cells are
+// drawn in row order from a generator seeded with the seed, one draw per
+// orbit of the symmetry group, so the same settings always give back the
+// same soup on a region of the same size.
+
+pub const DEFAULT_DENSITY: f64 = 0.35;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Symmetry {
+    None,
+    C2,
+    C4,
+    D4,
+    D8,
+}
+
+impl Symmetry {
+    const ALL: [Symmetry; 5] = [Symmetry::None, Symmetry::C2, Symmetry::C4, Symmetry::D4, Symmetry::D8];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::C2 => "C2",
+            Symmetry::C4 => "C4",
+            Symmetry::D4 => "D4",
+            Symmetry::D8 => "D8",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Symmetry> {
+        Symmetry::ALL.into_iter().find(|symmetry| symmetry.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn next(self) -> Symmetry {
+        let idx = Symmetry::ALL.iter().position(|s| *s == self).unwrap();
+        Symmetry::ALL[(idx + 1) % Symmetry::ALL.len()]
+    }
+
+    // C4 and D8 turn the region by a quarter, so it has to be a square \\
+    fn needs_square(self) -> bool {
+        matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+
+    // images of a cell of a w x h region under the group \\
+    fn orbit(self, u: i64, v: i64, w: i64, h: i64) -> Vec<(i64, i64)> {
+        let (mu, mv) = (w - 1 - u, h - 1 - v);
+        match self {
+            Symmetry::None => vec![(u, v)],
+            Symmetry::C2 => vec![(u, v), (mu, mv)],
+            Symmetry::C4 => vec![(u, v), (mv, u), (mu, mv), (v, mu)],
+            Symmetry::D4 => vec![(u, v), (mu, v), (u, mv), (mu, mv)],
+            Symmetry::D8 => vec![(u, v), (mv, u), (mu, mv), (v, mu), (mu, v), (u, mv), (v, u), (mv, mu)],
+        }
+    }
+}
+
+
+#[derive(Clone, Copy)]
+pub struct Soup {
+    pub density: f64,
+    pub symmetry: Symmetry,
+    pub seed: u64,
+}
+
+impl Soup {
+    pub fn new() -> Soup {
+        Soup {
+            density: DEFAULT_DENSITY,
+            symmetry: Symmetry::None,
+            seed: 0,
+        }
+    }
+
+    pub fn reseed(&mut self) {
+        self.seed = rand::thread_rng().gen();
+    }
+
+    pub fn describe(&self) -> String {
+        format!("soup seed {}, density {:.2}, symmetry {}", self.seed, self.density, self.symmetry.name())
+    }
+
+    // overwrites a region with the soup, squared off and centered if the symmetry needs it \\
+    pub fn fill(&self, population: &mut Population, x0: i64, y0: i64, x1: i64, y1: i64) {
+        let (mut x0, mut y0, mut w, mut h) = (x0, y0, x1 - x0 + 1, y1 - y0 + 1);
+        if self.symmetry.needs_square() {
+            let side = w.min(h);
+            x0 += (w - side) / 2;
+            y0 += (h - side) / 2;
+            (w, h) = (side, side);
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        for v in 0..h {
+            for u in 0..w {
+                let orbit = self.symmetry.orbit(u, v, w, h);
+                if orbit.iter().min() != Some(&(u, v)) {
+                    continue;
+                }
+                let alive = rng.gen_bool(self.density);
+                for (ou, ov) in orbit {
+                    population.set(x0 + ou, y0 + ov, alive);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(soup: &Soup) -> Vec<(i64, i64)> {
+        let mut population = Population::new();
+        soup.fill(&mut population, -8, -8, 7, 7);
+        let mut cells = population.live_cells();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn a_seed_gives_back_the_same_soup() {
+        let mut soup = Soup::new();
+        soup.seed = 1234;
+        let first = cells(&soup);
+        assert!(!first.is_empty());
+        assert_eq!(cells(&soup), first);
+        soup.seed = 1235;
+        assert_ne!(cells(&soup), first);
+    }
+
+    #[test]
+    fn symmetric_soups_are_symmetric() {
+        let mut soup = Soup::new();
+        soup.seed = 7;
+        soup.symmetry = Symmetry::D8;
+        let cells = cells(&soup);
+        for &(x, y) in &cells {
+            // the 16x16 region spans -8..=7, so mirroring maps x to -1 - x \\
+            assert!(cells.binary_search(&(-1 - x, y)).is_ok());
+            assert!(cells.binary_search(&(y, x)).is_ok());
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use super::Population;
+
+const CAPACITY: usize = 4096;
+
+
+#[derive(Clone, Copy)]
+pub struct Sample {
+    pub generation: u64,
+    pub population: u64,
+    pub births: u64,
+    pub deaths: u64,
+}
+
+
+// One sample per recorded step, ordered by generation. Going back in time
+// (undo, step back, clear) drops the samples of the abandoned future.
+pub struct Stats {
+    samples: VecDeque<Sample>,
+}
+
+impl Stats {
+    pub fn new(population: &Population) -> Stats {
+        let mut stats = Stats {samples: VecDeque::new()};
+        stats.push(Sample {generation: population.generation, population: population.count(), births: 0, deaths: 0});
+        stats
+    }
+
+    fn push(&mut self, sample: Sample) {
+        self.rewind(sample.generation.saturating_sub(1));
+        self.samples.push_back(sample);
+        if self.samples.len() > CAPACITY {
+            self.samples.pop_front();
+        }
+    }
+
+    // sample of a population compared to the one it was stepped from \\
+    pub fn record(&mut self, before: &Population, after: &Population) {
+        let (births, deaths) = after.changes_since(before);
+        self.push(Sample {generation: after.generation, population: after.count(), births, deaths});
+    }
+
+    // forgets the samples past a generation \\
+    pub fn rewind(&mut self, generation: u64) {
+        while self.samples.back().is_some_and(|s| s.generation > generation) {
+            self.samples.pop_back();
+        }
+    }
+
+    pub fn last(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    // population of the last `count` samples, oldest first \\
+    pub fn populations(&self, count: usize) -> Vec<u64> {
+        let skip = self.samples.len().saturating_sub(count);
+        self.samples.iter().skip(skip).map(|s| s.population).collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("generation,population,births,deaths\n");
+        for s in &self.samples {
+            csv.push_str(&format!("{},{},{},{}\n", s.generation, s.population, s.births, s.deaths));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a blinker, which keeps three cells and gives birth to two every step \\
+    fn blinker() -> Population {
+        let mut population = Population::new();
+        for x in 0..3 {
+            population.set(x, 0, true);
+        }
+        population
+    }
+
+    // steps `count` times, recording each generation \\
+    fn run(stats: &mut Stats, population: &mut Population, count: u64) {
+        for _ in 0..count {
+            let before = population.clone();
+            population.get_next_gen();
+            stats.record(&before, population);
+        }
+    }
+
+    fn generations(stats: &Stats) -> Vec<u64> {
+        stats.samples.iter().map(|s| s.generation).collect()
+    }
+
+    #[test]
+    fn records_births_and_deaths_of_each_step() {
+        let mut population = blinker();
+        let mut stats = Stats::new(&population);
+        run(&mut stats, &mut population, 3);
+        assert_eq!(generations(&stats), vec![0, 1, 2, 3]);
+        let last = stats.last().unwrap();
+        assert_eq!((last.population, last.births, last.deaths), (3, 2, 2));
+        assert_eq!(stats.populations(2), vec![3, 3]);
+        assert!(stats.to_csv().ends_with("\n3,3,2,2\n"));
+    }
+
+    #[test]
+    fn rewinding_drops_the_later_samples() {
+        let mut population = blinker();
+        let mut stats = Stats::new(&population);
+        run(&mut stats, &mut population, 5);
+        stats.rewind(2);
+        assert_eq!(generations(&stats), vec![0, 1, 2]);
+
+        // stepping again from an earlier generation replaces the abandoned future \\
+        let mut population = blinker();
+        run(&mut stats, &mut population, 1);
+        assert_eq!(generations(&stats), vec![0, 1]);
+    }
+
+    #[test]
+    fn keeps_the_newest_samples() {
+        let mut population = blinker();
+        let mut stats = Stats::new(&population);
+        run(&mut stats, &mut population, CAPACITY as u64 + 10);
+        assert_eq!(stats.samples.len(), CAPACITY);
+        assert_eq!(stats.samples.front().unwrap().generation, 11);
+    }
+}
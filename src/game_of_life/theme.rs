@@ -0,0 +1,80 @@
+use tui::style::Color;
+
+// Every color of the Life canvas comes from the current theme. Gradients
+// go from young to old cells and from quiet to busy ones.
+
+pub struct Theme {
+    pub name: &'static str,
+    pub live: Color,
+    pub cursor: Color,
+    pub ghost: Color,
+    pub selection: Color,
+    // beyond the edges of a bounded board \\
+    pub outside: Color,
+    // behind the cells of exported pictures \\
+    pub background: Color,
+    pub history: [Color; 2],
+    pub birth: Color,
+    pub death: Color,
+    pub age: [Color; 6],
+    pub heat: [Color; 6],
+}
+
+pub const THEMES: [Theme; 2] = [
+    Theme {
+        name: "teal",
+        live: Color::Rgb(0, 255, 255),
+        cursor: Color::White,
+        ghost: Color::Gray,
+        selection: Color::Rgb(40, 40, 90),
+        outside: Color::Rgb(25, 25, 35),
+        background: Color::Rgb(0, 0, 0),
+        history: [Color::Rgb(0, 100, 100), Color::Rgb(0, 50, 50)],
+        birth: Color::Rgb(180, 255, 180),
+        death: Color::Rgb(120, 30, 30),
+        age: [
+            Color::Rgb(255, 255, 255),
+            Color::Rgb(150, 255, 255),
+            Color::Rgb(0, 255, 255),
+            Color::Rgb(0, 190, 220),
+            Color::Rgb(0, 120, 200),
+            Color::Rgb(40, 60, 180),
+        ],
+        heat: [
+            Color::Rgb(40, 0, 80),
+            Color::Rgb(110, 0, 120),
+            Color::Rgb(190, 0, 90),
+            Color::Rgb(255, 70, 0),
+            Color::Rgb(255, 170, 0),
+            Color::Rgb(255, 255, 120),
+        ],
+    },
+    Theme {
+        name: "ember",
+        live: Color::Rgb(255, 190, 60),
+        cursor: Color::Rgb(120, 200, 255),
+        ghost: Color::Rgb(150, 120, 90),
+        selection: Color::Rgb(80, 40, 30),
+        outside: Color::Rgb(35, 20, 15),
+        background: Color::Rgb(20, 10, 5),
+        history: [Color::Rgb(130, 70, 20), Color::Rgb(70, 35, 10)],
+        birth: Color::Rgb(255, 255, 160),
+        death: Color::Rgb(90, 20, 60),
+        age: [
+            Color::Rgb(255, 255, 200),
+            Color::Rgb(255, 220, 100),
+            Color::Rgb(255, 170, 40),
+            Color::Rgb(230, 110, 20),
+            Color::Rgb(180, 60, 20),
+            Color::Rgb(120, 30, 20),
+        ],
+        heat: [
+            Color::Rgb(20, 40, 90),
+            Color::Rgb(30, 90, 140),
+            Color::Rgb(40, 150, 150),
+            Color::Rgb(120, 200, 100),
+            Color::Rgb(220, 230, 80),
+            Color::Rgb(255, 255, 255),
+        ],
+    },
+];
@@ -0,0 +1,275 @@
+use std::collections::{HashSet, VecDeque};
+
+// Shape tools draw between a mark dropped with the edit key and the cursor
+// position at the next press. Whatever a tool changes can be mirrored
+// across a vertical and/or horizontal axis running through the cell where
+// mirroring was switched on.
+
+const MAX_FILL: usize = 1 << 20;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tool {
+    Pencil,
+    Line,
+    Rectangle,
+    FilledRectangle,
+    Ellipse,
+    Fill,
+}
+
+impl Tool {
+    const ALL: [Tool; 6] = [Tool::Pencil, Tool::Line, Tool::Rectangle, Tool::FilledRectangle, Tool::Ellipse, Tool::Fill];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Pencil => "pencil",
+            Tool::Line => "line",
+            Tool::Rectangle => "rectangle",
+            Tool::FilledRectangle => "filled rectangle",
+            Tool::Ellipse => "ellipse",
+            Tool::Fill => "flood fill",
+        }
+    }
+
+    pub fn next(self) -> Tool {
+        let idx = Tool::ALL.iter().position(|t| *t == self).unwrap();
+        Tool::ALL[(idx + 1) % Tool::ALL.len()]
+    }
+
+    // cells of the shape spanned by two corners \\
+    pub fn shape(self, a: (i64, i64), b: (i64, i64)) -> Vec<(i64, i64)> {
+        self.shape_within(a, b, (a.0.min(b.0), a.1.min(b.1), a.0.max(b.0), a.1.max(b.1)))
+    }
+
+    // the cells of the shape that fall inside a clip rectangle; boxes and
+    // ellipses only visit the part of their box that overlaps the clip \\
+    pub fn shape_within(self, (ax, ay): (i64, i64), (bx, by): (i64, i64), clip: (i64, i64, i64, i64)) -> Vec<(i64, i64)> {
+        let (x0, y0, x1, y1) = (ax.min(bx), ay.min(by), ax.max(bx), ay.max(by));
+        let (cx0, cy0, cx1, cy1) = (x0.max(clip.0), y0.max(clip.1), x1.min(clip.2), y1.min(clip.3));
+        let overlap = move || (cx0..=cx1).flat_map(move |x| (cy0..=cy1).map(move |y| (x, y)));
+        match self {
+            Tool::Line => line((ax, ay), (bx, by)).into_iter().filter(|&cell| contains(clip, cell)).collect(),
+            Tool::Rectangle => overlap()
+                .filter(|&(x, y)| x == x0 || x == x1 || y == y0 || y == y1)
+                .collect(),
+            Tool::FilledRectangle => overlap().collect(),
+            Tool::Ellipse => ellipse((x0, y0, x1, y1), (cx0, cy0, cx1, cy1)),
+            Tool::Pencil | Tool::Fill => [(bx, by)].into_iter().filter(|&cell| contains(clip, cell)).collect(),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mirror {
+    Off,
+    Vertical(i64),
+    Horizontal(i64),
+    Both(i64, i64),
+}
+
+impl Mirror {
+    // next mode, with its axes through (x, y) \\
+    pub fn next(self, x: i64, y: i64) -> Mirror {
+        match self {
+            Mirror::Off => Mirror::Vertical(x),
+            Mirror::Vertical(_) => Mirror::Horizontal(y),
+            Mirror::Horizontal(_) => Mirror::Both(x, y),
+            Mirror::Both(_, _) => Mirror::Off,
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            Mirror::Off => String::from("mirror off"),
+            Mirror::Vertical(x) => format!("mirror across x = {}", x),
+            Mirror::Horizontal(y) => format!("mirror across y = {}", y),
+            Mirror::Both(x, y) => format!("mirror across x = {} and y = {}", x, y),
+        }
+    }
+
+    // a cell and its reflections, without duplicates \\
+    pub fn images(self, (x, y): (i64, i64)) -> Vec<(i64, i64)> {
+        let mut images = match self {
+            Mirror::Off => vec![(x, y)],
+            Mirror::Vertical(ax) => vec![(x, y), (2 * ax - x, y)],
+            Mirror::Horizontal(ay) => vec![(x, y), (x, 2 * ay - y)],
+            Mirror::Both(ax, ay) => vec![(x, y), (2 * ax - x, y), (x, 2 * ay - y), (2 * ax - x, 2 * ay - y)],
+        };
+        images.sort_unstable();
+        images.dedup();
+        images
+    }
+
+    pub fn apply(self, cells: &[(i64, i64)]) -> Vec<(i64, i64)> {
+        let mut images: Vec<(i64, i64)> = cells.iter().flat_map(|&cell| self.images(cell)).collect();
+        images.sort_unstable();
+        images.dedup();
+        images
+    }
+
+    // the rectangles whose cells are reflected into a given one, itself included \\
+    pub fn sources(self, (x0, y0, x1, y1): (i64, i64, i64, i64)) -> Vec<(i64, i64, i64, i64)> {
+        let (a, b) = (self.images((x0, y0)), self.images((x1, y1)));
+        a.into_iter().zip(b)
+            .map(|((ax, ay), (bx, by))| (ax.min(bx), ay.min(by), ax.max(bx), ay.max(by)))
+            .collect()
+    }
+}
+
+
+// shape that the next press of the edit key would draw, mirrored and cut down
+// to a clip rectangle so that only what is on screen gets generated \\
+pub fn preview(tool: Tool, mirror: Mirror, mark: (i64, i64), cursor: (i64, i64), clip: (i64, i64, i64, i64)) -> Vec<(i64, i64)> {
+    let cells: Vec<(i64, i64)> = mirror.sources(clip).into_iter()
+        .flat_map(|area| tool.shape_within(mark, cursor, area))
+        .collect();
+    mirror.apply(&cells).into_iter().filter(|&cell| contains(clip, cell)).collect()
+}
+
+fn contains((x0, y0, x1, y1): (i64, i64, i64, i64), (x, y): (i64, i64)) -> bool {
+    x >= x0 && y >= y0 && x <= x1 && y <= y1
+}
+
+
+// Bresenham \\
+fn line((x0, y0): (i64, i64), (x1, y1): (i64, i64)) -> Vec<(i64, i64)> {
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+    let mut cells = vec![(x, y)];
+    while (x, y) != (x1, y1) {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        cells.push((x, y));
+    }
+    cells
+}
+
+// outline of the ellipse inscribed in a box: inside cells with a 4-neighbor
+// outside, looked for only within the part of the box given as the second
+// rectangle \\
+fn ellipse((x0, y0, x1, y1): (i64, i64, i64, i64), (cx0, cy0, cx1, cy1): (i64, i64, i64, i64)) -> Vec<(i64, i64)> {
+    let (rx, ry) = ((x1 - x0 + 1) as f64 / 2.0, (y1 - y0 + 1) as f64 / 2.0);
+    let (cx, cy) = ((x0 + x1) as f64 / 2.0, (y0 + y1) as f64 / 2.0);
+    let inside = |x: i64, y: i64| {
+        let (u, v) = ((x as f64 - cx) / rx, (y as f64 - cy) / ry);
+        u * u + v * v <= 1.0
+    };
+    (cx0..=cx1).flat_map(|x| (cy0..=cy1).map(move |y| (x, y)))
+        .filter(|&(x, y)| inside(x, y))
+        .filter(|&(x, y)| !(inside(x - 1, y) && inside(x + 1, y) && inside(x, y - 1) && inside(x, y + 1)))
+        .collect()
+}
+
+// free cells 4-connected to a start cell, none if the region reaches past the
+// frame when it is open; the edges of a walled frame (a board) stop it \\
+pub fn flood_fill(start: (i64, i64), (x0, y0, x1, y1): (i64, i64, i64, i64), walled: bool, blocked: impl Fn(i64, i64) -> bool) -> Option<Vec<(i64, i64)>> {
+    let within = |cell: (i64, i64)| contains((x0, y0, x1, y1), cell);
+    if blocked(start.0, start.1) || !within(start) {
+        return None;
+    }
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        for next in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+            if blocked(next.0, next.1) || seen.contains(&next) {
+                continue;
+            }
+            if !within(next) && walled {
+                continue;
+            }
+            if !within(next) || seen.len() >= MAX_FILL {
+                return None;
+            }
+            seen.insert(next);
+            queue.push_back(next);
+        }
+    }
+    Some(seen.into_iter().collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut cells: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn lines_join_their_endpoints_in_every_octant() {
+        for (dx, dy) in [(7, 3), (3, 7), (-3, 7), (-7, 3), (-7, -3), (-3, -7), (3, -7), (7, -3)] {
+            let cells = line((2, -1), (2 + dx, -1 + dy));
+            assert_eq!(cells.first(), Some(&(2, -1)));
+            assert_eq!(cells.last(), Some(&(2 + dx, -1 + dy)));
+            assert_eq!(cells.len() as i64, dx.abs().max(dy.abs()) + 1);
+            for pair in cells.windows(2) {
+                let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+                assert!((x1 - x0).abs() <= 1 && (y1 - y0).abs() <= 1 && (x0, y0) != (x1, y1));
+            }
+        }
+        assert_eq!(line((4, 4), (4, 4)), vec![(4, 4)]);
+    }
+
+    #[test]
+    fn ellipses_are_symmetric_about_both_axes() {
+        for (x1, y1) in [(10, 6), (11, 7), (4, 9), (0, 0)] {
+            let cells = Tool::Ellipse.shape((0, 0), (x1, y1));
+            assert!(!cells.is_empty());
+            let flipped = |f: &dyn Fn((i64, i64)) -> (i64, i64)| sorted(cells.iter().map(|&c| f(c)).collect());
+            assert_eq!(flipped(&|(x, y)| (x1 - x, y)), sorted(cells.clone()));
+            assert_eq!(flipped(&|(x, y)| (x, y1 - y)), sorted(cells.clone()));
+            assert!(cells.iter().all(|&(x, y)| (0..=x1).contains(&x) && (0..=y1).contains(&y)));
+        }
+    }
+
+    #[test]
+    fn flood_fills_stop_at_live_cells() {
+        // a 5x5 ring of live cells around a 3x3 hole \\
+        let ring = |x: i64, y: i64| (0..=4).contains(&x) && (0..=4).contains(&y) && (x == 0 || x == 4 || y == 0 || y == 4);
+        let inner = flood_fill((2, 2), (-10, -10, 10, 10), false, ring).unwrap();
+        assert_eq!(sorted(inner), (1..=3).flat_map(|x| (1..=3).map(move |y| (x, y))).collect::<Vec<_>>());
+        assert!(flood_fill((0, 0), (-10, -10, 10, 10), false, ring).is_none());
+        assert!(flood_fill((6, 6), (-10, -10, 10, 10), false, ring).is_none());
+        let outer = flood_fill((6, 6), (-2, -2, 6, 6), true, ring).unwrap();
+        assert_eq!(outer.len(), 9 * 9 - 25);
+    }
+
+    #[test]
+    fn each_mirror_mode_reflects_across_its_axes() {
+        let cells = [(1, 2), (3, 3)];
+        assert_eq!(Mirror::Off.apply(&cells), vec![(1, 2), (3, 3)]);
+        assert_eq!(Mirror::Vertical(0).apply(&cells), vec![(-3, 3), (-1, 2), (1, 2), (3, 3)]);
+        assert_eq!(Mirror::Horizontal(3).apply(&cells), vec![(1, 2), (1, 4), (3, 3)]);
+        assert_eq!(Mirror::Both(2, 0).apply(&[(1, 2)]), vec![(1, -2), (1, 2), (3, -2), (3, 2)]);
+        let mut mirror = Mirror::Off;
+        for _ in 0..4 {
+            mirror = mirror.next(5, 6);
+        }
+        assert!(mirror == Mirror::Off);
+    }
+
+    #[test]
+    fn previews_hold_the_visible_part_of_the_mirrored_shape() {
+        let clip = (0, 0, 9, 9);
+        for tool in [Tool::Line, Tool::Rectangle, Tool::FilledRectangle, Tool::Ellipse, Tool::Pencil] {
+            for mirror in [Mirror::Off, Mirror::Vertical(-4), Mirror::Horizontal(12), Mirror::Both(-4, 12)] {
+                let expected: Vec<(i64, i64)> = mirror.apply(&tool.shape((-30, -5), (6, 20))).into_iter()
+                    .filter(|&cell| contains(clip, cell))
+                    .collect();
+                assert_eq!(preview(tool, mirror, (-30, -5), (6, 20), clip), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use std::{io, collections::{HashMap, VecDeque}, str::FromStr};
+use tui::{
+    backend::Backend,
+    widgets::canvas::Context,
+    style::Color,
+    text::Span,
+    Terminal
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use super::{Cursor, paint_population, prompt::{Prompt, Ask}, mode::{Mode, Controls, run_mode}};
+
+// Turmites walk the grid, each in an internal state: the color under a
+// turmite picks what it writes, how it turns and its next state before it
+// steps forward. Langton's ant and its multi-color relatives are the one
+// state turmites written as turn strings ("RL", "LLRR"), one letter per
+// color. General tables use Golly's notation, {{{write, turn, state}, ...}}
+// with a triple per color nested in a list per state, where turns are
+// 1 (none), 2 (right), 4 (u-turn) and 8 (left).
+
+const HELP: &str = "[a]: 'add/remove ant', [r]: 'rule', [tab]: 'next preset', [p]: 'pause/resume', [n]: 'step', [c]: 'clear', [arrows]: 'move cursor', [shift+arrows]: 'pan', [i/o]: 'zoom in/out', [0]: 'fit', [+/-]: 'faster/slower', [q]: 'quit', [?]: 'all keys'";
+const PRESETS: [&str; 6] = ["RL", "RLR", "LLRR", "LRRRRRLLR", "RRLLLRLLLRRR", "{{{1, 8, 1}, {1, 8, 1}}, {{1, 2, 1}, {0, 1, 0}}}"];
+const COLORS: [Color; 12] = [
+    Color::Rgb(0, 200, 200),
+    Color::Rgb(230, 170, 0),
+    Color::Rgb(60, 140, 255),
+    Color::Rgb(120, 220, 80),
+    Color::Rgb(220, 80, 200),
+    Color::Rgb(255, 120, 60),
+    Color::Rgb(150, 150, 255),
+    Color::Rgb(200, 200, 200),
+    Color::Rgb(255, 220, 120),
+    Color::Rgb(40, 160, 120),
+    Color::Rgb(180, 100, 60),
+    Color::Rgb(120, 120, 120),
+];
+const ANT_COLOR: Color = Color::Rgb(255, 40, 40);
+// steps of every ant kept to look for a highway, and the longest period sought \\
+const TRAIL: usize = 4096;
+const MAX_PERIOD: usize = 1024;
+const CHECK_EVERY: u64 = 256;
+// north, east, south and west, y growing upward \\
+const HEADINGS: [(i64, i64); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+
+#[derive(Clone, Copy, PartialEq)]
+struct Transition {
+    write: u8,
+    // quarter turns to the right \\
+    turn: u8,
+    next: usize,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Turmite {
+    name: String,
+    table: Vec<Vec<Transition>>,
+}
+
+impl Turmite {
+    pub fn colors(&self) -> usize {
+        self.table[0].len()
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// a turn string, or a table of nested braces \\
+impl FromStr for Turmite {
+    type Err = io::Error;
+
+    fn from_str(text: &str) -> io::Result<Turmite> {
+        let text = text.trim();
+        let invalid_rule = || invalid(format!("invalid turmite rule '{}'", text));
+        if !text.starts_with('{') {
+            let turns = text.to_ascii_uppercase().chars()
+                .map(|c| match c {
+                    'N' => Some(0),
+                    'R' => Some(1),
+                    'U' => Some(2),
+                    'L' => Some(3),
+                    _ => None,
+                })
+                .collect::<Option<Vec<u8>>>()
+                .filter(|turns| (2..=COLORS.len()).contains(&turns.len()))
+                .ok_or_else(invalid_rule)?;
+            let colors = turns.len();
+            let table = vec![turns.iter().enumerate()
+                .map(|(color, &turn)| Transition {write: ((color + 1) % colors) as u8, turn, next: 0})
+                .collect()];
+            return Ok(Turmite {name: text.to_ascii_uppercase(), table});
+        }
+
+        // numbers grouped by the depth of the braces around them \\
+        let mut states: Vec<Vec<Vec<u32>>> = Vec::new();
+        let (mut depth, mut number) = (0, String::new());
+        for c in text.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            if !number.is_empty() {
+                let value = number.parse().map_err(|_| invalid_rule())?;
+                states.last_mut().and_then(|colors| colors.last_mut()).ok_or_else(invalid_rule)?.push(value);
+                number.clear();
+            }
+            match c {
+                '{' => {
+                    depth += 1;
+                    match depth {
+                        2 => states.push(Vec::new()),
+                        3 => states.last_mut().ok_or_else(invalid_rule)?.push(Vec::new()),
+                        1 => {}
+                        _ => return Err(invalid_rule()),
+                    }
+                }
+                '}' => depth -= 1,
+                ',' | ' ' => {}
+                _ => return Err(invalid_rule()),
+            }
+        }
+
+        let colors = states.first().map_or(0, Vec::len);
+        if depth != 0 || !(2..=COLORS.len()).contains(&colors) || states.iter().any(|state| state.len() != colors) {
+            return Err(invalid_rule());
+        }
+        let table = states.iter()
+            .map(|state| state.iter()
+                .map(|triple| match triple[..] {
+                    [write, turn, next] if (write as usize) < colors && (next as usize) < states.len() => Ok(Transition {
+                        write: write as u8,
+                        turn: match turn {1 => 0, 2 => 1, 4 => 2, 8 => 3, _ => return Err(invalid_rule())},
+                        next: next as usize,
+                    }),
+                    _ => Err(invalid_rule()),
+                })
+                .collect::<io::Result<Vec<Transition>>>())
+            .collect::<io::Result<Vec<Vec<Transition>>>>()?;
+        Ok(Turmite {name: String::from(text), table})
+    }
+}
+
+
+// a turmite settled into repeating the same moves while drifting away \\
+#[derive(Clone, Copy, PartialEq)]
+pub struct Highway {
+    period: usize,
+    dx: i64,
+    dy: i64,
+    step: u64,
+}
+
+struct Ant {
+    x: i64,
+    y: i64,
+    heading: u8,
+    state: usize,
+    // (state, heading and color read) with the position before each step \\
+    trail: VecDeque<(usize, i64, i64)>,
+    highway: Option<Highway>,
+}
+
+impl Ant {
+    fn new(x: i64, y: i64) -> Ant {
+        Ant {x, y, heading: 0, state: 0, trail: VecDeque::new(), highway: None}
+    }
+
+    // shortest period over which the moves repeat with the same nonzero drift, three times over \\
+    fn find_highway(&self, step: u64) -> Option<Highway> {
+        let trail = &self.trail;
+        (1..=MAX_PERIOD.min(trail.len().saturating_sub(1) / 4)).find_map(|period| {
+            let last = trail.len() - 1;
+            let (dx, dy) = (trail[last].1 - trail[last - period].1, trail[last].2 - trail[last - period].2);
+            let repeats = (last - 3 * period..=last).all(|t| {
+                let (now, before) = (trail[t], trail[t - period]);
+                now.0 == before.0 && now.1 - before.1 == dx && now.2 - before.2 == dy
+            });
+            (repeats && (dx, dy) != (0, 0)).then_some(Highway {period, dx, dy, step})
+        })
+    }
+}
+
+
+struct Colony {
+    turmite: Turmite,
+    grid: HashMap<(i64, i64), u8>,
+    ants: Vec<Ant>,
+    steps: u64,
+}
+
+impl Colony {
+    fn new(turmite: Turmite) -> Colony {
+        Colony {turmite, grid: HashMap::new(), ants: vec![Ant::new(0, 0)], steps: 0}
+    }
+
+    fn step(&mut self) {
+        let colors = self.turmite.colors();
+        for ant in &mut self.ants {
+            let color = self.grid.get(&(ant.x, ant.y)).copied().unwrap_or(0);
+            let transition = self.turmite.table[ant.state][color as usize];
+            ant.trail.push_back(((ant.state * 4 + ant.heading as usize) * colors + color as usize, ant.x, ant.y));
+            if ant.trail.len() > TRAIL {
+                ant.trail.pop_front();
+            }
+
+            match transition.write {
+                0 => self.grid.remove(&(ant.x, ant.y)),
+                write => self.grid.insert((ant.x, ant.y), write),
+            };
+            ant.heading = (ant.heading + transition.turn) % 4;
+            ant.state = transition.next;
+            let (dx, dy) = HEADINGS[ant.heading as usize];
+            ant.x += dx;
+            ant.y += dy;
+        }
+        self.steps += 1;
+
+        if self.steps.is_multiple_of(CHECK_EVERY) {
+            let steps = self.steps;
+            for ant in self.ants.iter_mut().filter(|ant| ant.highway.is_none()) {
+                ant.highway = ant.find_highway(steps);
+            }
+        }
+    }
+
+    // removes the ant under the cursor, or puts a new one there \\
+    fn toggle_ant(&mut self, x: i64, y: i64) {
+        match self.ants.iter().position(|ant| (ant.x, ant.y) == (x, y)) {
+            Some(idx) => {
+                self.ants.remove(idx);
+            }
+            None => self.ants.push(Ant::new(x, y)),
+        }
+    }
+
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        self.grid.keys().copied()
+            .chain(self.ants.iter().map(|ant| (ant.x, ant.y)))
+            .fold(None, |bounds, (x, y)| match bounds {
+                None => Some((x, y, x, y)),
+                Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y))),
+            })
+    }
+
+    fn highways(&self) -> Vec<String> {
+        self.ants.iter().enumerate()
+            .filter_map(|(idx, ant)| ant.highway.map(|highway| (idx, highway)))
+            .map(|(idx, highway)| {
+                let ant = if self.ants.len() > 1 {format!("ant {} ", idx + 1)} else {String::new()};
+                format!(
+                    "{}building a highway: period {}, moving ({}, {}), noticed at step {}",
+                    ant, highway.period, highway.dx, highway.dy, highway.step,
+                )
+            })
+            .collect()
+    }
+}
+
+
+const USAGE: &str = "usage: game_collection ant [rule]";
+
+pub fn run_turmites<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+    run_colony(terminal, Colony::new(PRESETS[0].parse()?))
+}
+
+pub fn run_turmites_cli(args: &[String]) -> io::Result<()> {
+    let colony = match args {
+        [] => Colony::new(PRESETS[0].parse()?),
+        [rule] if !rule.starts_with('-') => Colony::new(rule.parse()?),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, USAGE)),
+    };
+    crate::with_terminal(|terminal| run_colony(terminal, colony))
+}
+
+fn run_colony<B: Backend>(terminal: &mut Terminal<B>, colony: Colony) -> io::Result<()> {
+    let preset = PRESETS.iter().position(|preset| preset.parse().ok().as_ref() == Some(&colony.turmite)).unwrap_or(PRESETS.len() - 1);
+    let controls = Controls::new(terminal.size()?, Some(Cursor {x: 0, y: 0}));
+    run_mode(terminal, &mut Turmites {colony, preset}, controls)
+}
+
+struct Turmites {
+    colony: Colony,
+    preset: usize,
+}
+
+impl Mode for Turmites {
+    fn name(&self) -> String {
+        String::from("Langton's Ant")
+    }
+
+    fn help(&self) -> &'static str {
+        HELP
+    }
+
+    fn stats(&self) -> Vec<Span<'static>> {
+        let colony = &self.colony;
+        vec![Span::raw(format!("Steps: {} | Ants: {} | Rule: {}", colony.steps, colony.ants.len(), colony.turmite.name))]
+    }
+
+    fn notes(&self) -> Vec<String> {
+        self.colony.highways()
+    }
+
+    fn paint(&self, ctx: &mut Context, controls: &Controls) {
+        let view = &controls.view;
+        let (w, h) = view.span();
+        let (x0, y0, x1, y1) = (view.x, view.y, view.x + w - 1, view.y + h - 1);
+        let mut by_color: Vec<Vec<(i64, i64)>> = vec![Vec::new(); COLORS.len()];
+        for (&(x, y), &color) in &self.colony.grid {
+            if x >= x0 && y >= y0 && x <= x1 && y <= y1 {
+                by_color[color as usize - 1].push((x, y));
+            }
+        }
+        for (cells, color) in by_color.iter().zip(COLORS) {
+            paint_population(ctx, view, cells, color);
+        }
+        let ants: Vec<(i64, i64)> = self.colony.ants.iter().map(|ant| (ant.x, ant.y)).collect();
+        paint_population(ctx, view, &ants, ANT_COLOR);
+    }
+
+    fn step(&mut self) {
+        self.colony.step();
+    }
+
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        self.colony.bounding_box()
+    }
+
+    fn answer(&mut self, prompt: &Prompt) -> Result<(), String> {
+        let turmite = prompt.text.parse::<Turmite>().map_err(|err| err.to_string())?;
+        self.colony = Colony::new(turmite);
+        Ok(())
+    }
+
+    fn key(&mut self, key: KeyEvent, controls: &mut Controls) -> io::Result<bool> {
+        match key.code {
+            KeyCode::Char('c') => {
+                self.colony = Colony::new(self.colony.turmite.clone());
+                controls.paused = true;
+            }
+            KeyCode::Char('a') => if let Some(cursor) = &controls.cursor {
+                self.colony.toggle_ant(cursor.x, cursor.y);
+            }
+            KeyCode::Char('r') => controls.prompt = Some(Prompt::new(Ask::TurmiteRule)),
+            KeyCode::Tab => {
+                self.preset = (self.preset + 1) % PRESETS.len();
+                self.colony = Colony::new(PRESETS[self.preset].parse()?);
+                controls.paused = true;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turn_strings_cycle_through_the_colors() {
+        let turmite: Turmite = "rlr".parse().unwrap();
+        assert_eq!(turmite.name, "RLR");
+        assert_eq!(turmite.colors(), 3);
+        let writes: Vec<u8> = turmite.table[0].iter().map(|transition| transition.write).collect();
+        let turns: Vec<u8> = turmite.table[0].iter().map(|transition| transition.turn).collect();
+        assert_eq!(writes, vec![1, 2, 0]);
+        assert_eq!(turns, vec![1, 3, 1]);
+    }
+
+    #[test]
+    fn tables_match_their_turn_strings() {
+        let table: Turmite = "{{{1, 2, 0}, {0, 8, 0}}}".parse().unwrap();
+        let string: Turmite = "RL".parse().unwrap();
+        assert!(table.table == string.table);
+
+        let turmite: Turmite = PRESETS[5].parse().unwrap();
+        assert_eq!((turmite.table.len(), turmite.colors()), (2, 2));
+        assert!(turmite.table[1][1] == Transition {write: 0, turn: 0, next: 0});
+    }
+
+    #[test]
+    fn rejects_broken_rules() {
+        for rule in ["", "R", "RXL", "RLRLRLRLRLRLR", "{{{1, 2, 0}}}", "{{{1, 3, 0}, {0, 8, 0}}}", "{{{2, 2, 0}, {0, 8, 0}}}",
+            "{{{1, 2, 1}, {0, 8, 0}}}", "{{{1, 2, 0}, {0, 8, 0}}", "{{{1, 2, 0}, {0, 8}}}", "{{{{1, 2, 0}, {0, 8, 0}}}}"] {
+            assert!(rule.parse::<Turmite>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn langtons_ant_builds_its_highway() {
+        let mut colony = Colony::new("RL".parse().unwrap());
+        colony.step();
+        assert_eq!(colony.grid.get(&(0, 0)), Some(&1));
+        assert_eq!((colony.ants[0].x, colony.ants[0].y), (1, 0));
+
+        for _ in 1..12_000 {
+            colony.step();
+        }
+        let highway = colony.ants[0].highway.unwrap();
+        assert_eq!(highway.period, 104);
+        assert_eq!(highway.dx.abs(), 2);
+        assert_eq!(highway.dy.abs(), 2);
+    }
+}
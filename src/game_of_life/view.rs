@@ -0,0 +1,223 @@
+use crate::render::Renderer;
+
+// Window over the unbounded universe. Sizes are in terminal characters,
+// positions in world cells. A positive scale draws each cell over several
+// characters, a negative one packs several cells into each character. A
+// renderer other than blocks draws cells as the dots of half-block or
+// Braille glyphs, each dot standing for a square of cells, so it stops
+// zooming in at scale 0.
+
+pub const MIN_SCALE: i32 = -4;
+pub const MAX_SCALE: i32 = 2;
+
+
+pub struct Viewport {
+    pub x: i64,
+    pub y: i64,
+    pub scale: i32,
+    pub cols: i64,
+    pub rows: i64,
+    pub renderer: Renderer,
+}
+
+impl Viewport {
+    pub fn new(cols: i64, rows: i64) -> Viewport {
+        let mut view = Viewport {x: 0, y: 0, scale: 0, cols, rows, renderer: Renderer::Block};
+        view.center_on(0, 0);
+        view
+    }
+
+    pub fn resize(&mut self, cols: i64, rows: i64) {
+        self.cols = cols.max(1);
+        self.rows = rows.max(1);
+    }
+
+    // characters per cell when zoomed in, cells per character when zoomed out \\
+    pub fn factor(&self) -> i64 {
+        1 << self.scale.abs()
+    }
+
+    // whether cells are drawn as the dots of the renderer \\
+    pub fn dotted(&self) -> bool {
+        self.renderer != Renderer::Block
+    }
+
+    fn max_scale(&self) -> i32 {
+        if self.dotted() {0} else {MAX_SCALE}
+    }
+
+    // cells covered by one character, unless a cell covers several characters \\
+    fn char_cells(&self) -> Option<(i64, i64)> {
+        let k = self.factor();
+        if self.dotted() {
+            let (cols, rows) = self.renderer.dots();
+            Some((cols * k, rows * k))
+        } else if self.scale < 0 {
+            Some((k, k))
+        } else {
+            None
+        }
+    }
+
+    // cells crossed by one character step of the cursor \\
+    pub fn cell_step(&self) -> i64 {
+        if self.scale < 0 {self.factor()} else {1}
+    }
+
+    // number of cells visible horizontally and vertically \\
+    pub fn span(&self) -> (i64, i64) {
+        let k = self.factor();
+        match self.char_cells() {
+            Some((cw, ch)) => (self.cols * cw, self.rows * ch),
+            None => ((self.cols + k - 1) / k, (self.rows + k - 1) / k),
+        }
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        let (w, h) = self.span();
+        x >= self.x && y >= self.y && x < self.x + w && y < self.y + h
+    }
+
+    // bottom left character covered by a cell \\
+    pub fn to_screen(&self, x: i64, y: i64) -> Option<(i64, i64)> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        let k = self.factor();
+        let (dx, dy) = (x - self.x, y - self.y);
+        let (sx, sy) = match self.char_cells() {
+            Some((cw, ch)) => (dx / cw, dy / ch),
+            None => (dx * k, dy * k),
+        };
+        if sx < self.cols && sy < self.rows {Some((sx, sy))} else {None}
+    }
+
+    // cells covered by a character, as (min_x, min_y, max_x, max_y) \\
+    pub fn to_world(&self, sx: i64, sy: i64) -> (i64, i64, i64, i64) {
+        let k = self.factor();
+        match self.char_cells() {
+            Some((cw, ch)) => {
+                let (x, y) = (self.x + sx * cw, self.y + sy * ch);
+                (x, y, x + cw - 1, y + ch - 1)
+            }
+            None => {
+                let (x, y) = (self.x + sx / k, self.y + sy / k);
+                (x, y, x, y)
+            }
+        }
+    }
+
+    pub fn center_on(&mut self, x: i64, y: i64) {
+        let (w, h) = self.span();
+        self.x = x - w / 2;
+        self.y = y - h / 2;
+    }
+
+    // scrolls just enough to bring a cell into view \\
+    pub fn follow(&mut self, x: i64, y: i64) {
+        let (w, h) = self.span();
+        self.x = self.x.min(x).max(x - w + 1);
+        self.y = self.y.min(y).max(y - h + 1);
+    }
+
+    // moves by a quarter of the visible area in each direction \\
+    pub fn pan(&mut self, dx: i64, dy: i64) {
+        let (w, h) = self.span();
+        self.x += dx * (w / 4).max(1);
+        self.y += dy * (h / 4).max(1);
+    }
+
+    // keeps a cell at the center of the view while changing the scale \\
+    pub fn zoom(&mut self, delta: i32, x: i64, y: i64) {
+        self.scale = (self.scale + delta).clamp(MIN_SCALE, self.max_scale());
+        self.center_on(x, y);
+    }
+
+    // largest scale showing the whole box, centered on it \\
+    pub fn fit(&mut self, (x0, y0, x1, y1): (i64, i64, i64, i64)) {
+        self.scale = self.max_scale();
+        loop {
+            let (w, h) = self.span();
+            if (x1 - x0 < w && y1 - y0 < h) || self.scale == MIN_SCALE {
+                break;
+            }
+            self.scale -= 1;
+        }
+        self.center_on((x0 + x1) / 2, (y0 + y1) / 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view_at(x: i64, y: i64) -> Viewport {
+        let mut view = Viewport::new(80, 40);
+        view.center_on(x, y);
+        view
+    }
+
+    #[test]
+    fn zooming_in_then_out_restores_the_view() {
+        for scale in MIN_SCALE..MAX_SCALE {
+            let mut view = view_at(5, -7);
+            view.zoom(scale, 5, -7);
+            let (x, y) = (view.x, view.y);
+            view.zoom(1, 5, -7);
+            view.zoom(-1, 5, -7);
+            assert_eq!((view.x, view.y, view.scale), (x, y, scale));
+        }
+    }
+
+    #[test]
+    fn zooming_keeps_the_focus_in_the_middle() {
+        let mut view = view_at(0, 0);
+        for delta in [1, 1, -1, -1, -1, -1] {
+            view.zoom(delta, 100, 50);
+            let (sx, sy) = view.to_screen(100, 50).unwrap();
+            assert!((sx - 40).abs() <= view.factor() && (sy - 20).abs() <= view.factor());
+        }
+    }
+
+    #[test]
+    fn zoom_stops_at_the_scale_limits() {
+        let mut view = view_at(0, 0);
+        view.zoom(10, 0, 0);
+        assert_eq!(view.scale, MAX_SCALE);
+        view.zoom(-10, 0, 0);
+        assert_eq!(view.scale, MIN_SCALE);
+        view.renderer = Renderer::Braille;
+        view.zoom(10, 0, 0);
+        assert_eq!(view.scale, 0);
+    }
+
+    #[test]
+    fn characters_and_cells_map_onto_each_other() {
+        for scale in MIN_SCALE..=MAX_SCALE {
+            let mut view = view_at(3, 4);
+            view.zoom(scale, 3, 4);
+            for (x, y) in [(3, 4), (-2, 7), (view.x, view.y)] {
+                let (sx, sy) = view.to_screen(x, y).unwrap();
+                let (x0, y0, x1, y1) = view.to_world(sx, sy);
+                assert!(x0 <= x && x <= x1 && y0 <= y && y <= y1);
+            }
+        }
+    }
+
+    #[test]
+    fn panning_moves_a_quarter_of_the_view() {
+        let mut view = view_at(0, 0);
+        let (x, y) = (view.x, view.y);
+        view.pan(1, -1);
+        assert_eq!((view.x, view.y), (x + 20, y - 10));
+    }
+
+    #[test]
+    fn fitting_shows_the_whole_box() {
+        let mut view = view_at(0, 0);
+        view.fit((-100, -30, 150, 90));
+        assert!(view.contains(-100, -30) && view.contains(150, 90));
+        view.zoom(1, 25, 30);
+        assert!(!view.contains(-100, -30) || !view.contains(150, 90));
+    }
+}
@@ -0,0 +1,494 @@
+use std::{io, fs, mem, collections::HashMap, path::Path};
+use tui::{
+    backend::Backend,
+    widgets::canvas::Context,
+    style::{Color, Style},
+    text::Span,
+    Terminal
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use super::{Cursor, paint_population, tools::{self, Tool, Mirror}, history::{History, Change, Revision}, mode::{Mode, Controls, run_mode}};
+
+// Wireworld on the Life editor: every cell is empty, a conductor, an
+// electron head or an electron tail. Heads become tails, tails become
+// conductors again, and a conductor turns into a head when one or two of
+// its eight neighbors are heads. Only non-empty cells are stored.
+
+const HELP: &str = "[s]: 'paint', [tab]: 'next brush', [1-4]: 'brush', [p]: 'pause/resume', [n]: 'step', [c]: 'clear', [u/U]: 'undo/redo', [b]: 'step back', [arrows]: 'move cursor', [shift+arrows]: 'pan', [i/o]: 'zoom in/out', [0]: 'fit to circuit', [+/-]: 'faster/slower', [w]: 'drawing tool', [M]: 'mirror', [q]: 'quit', [?]: 'all keys'";
+const USAGE: &str = "usage:
+    game_collection wireworld [pattern]
+
+pattern formats: Golly RLE with rule = WireWorld (.rle), MCell (.mcl)";
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum State {
+    Empty,
+    Conductor,
+    Head,
+    Tail,
+}
+
+impl State {
+    const ALL: [State; 4] = [State::Empty, State::Conductor, State::Head, State::Tail];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            State::Empty => "empty",
+            State::Conductor => "conductor",
+            State::Head => "electron head",
+            State::Tail => "electron tail",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            State::Empty => Color::DarkGray,
+            State::Conductor => Color::Rgb(230, 170, 0),
+            State::Head => Color::Rgb(60, 140, 255),
+            State::Tail => Color::Rgb(255, 70, 40),
+        }
+    }
+
+    fn next(self) -> State {
+        let idx = State::ALL.iter().position(|s| *s == self).unwrap();
+        State::ALL[(idx + 1) % State::ALL.len()]
+    }
+
+    // numbering shared by Golly and MCell \\
+    fn from_number(number: u32) -> Option<State> {
+        match number {
+            0 => Some(State::Empty),
+            1 => Some(State::Head),
+            2 => Some(State::Tail),
+            3 => Some(State::Conductor),
+            _ => None,
+        }
+    }
+}
+
+
+#[derive(Clone)]
+pub struct Circuit {
+    cells: HashMap<(i64, i64), State>,
+    generation: u64,
+}
+
+impl Circuit {
+    pub fn new() -> Circuit {
+        Circuit {cells: HashMap::new(), generation: 0}
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> State {
+        self.cells.get(&(x, y)).copied().unwrap_or(State::Empty)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, state: State) {
+        match state {
+            State::Empty => self.cells.remove(&(x, y)),
+            state => self.cells.insert((x, y), state),
+        };
+    }
+
+    pub fn step(&mut self) {
+        let mut heads_around: HashMap<(i64, i64), u8> = HashMap::new();
+        for (&(x, y), _) in self.cells.iter().filter(|(_, state)| **state == State::Head) {
+            for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                *heads_around.entry((x + dx, y + dy)).or_insert(0) += 1;
+            }
+        }
+        for (cell, state) in self.cells.iter_mut() {
+            *state = match *state {
+                State::Head => State::Tail,
+                State::Tail => State::Conductor,
+                State::Conductor if matches!(heads_around.get(cell), Some(1 | 2)) => State::Head,
+                state => state,
+            };
+        }
+        self.generation += 1;
+    }
+
+    pub fn count(&self, state: State) -> usize {
+        self.cells.values().filter(|s| **s == state).count()
+    }
+
+    pub fn cells_in(&self, state: State, (x0, y0, x1, y1): (i64, i64, i64, i64)) -> Vec<(i64, i64)> {
+        self.cells.iter()
+            .filter(|(&(x, y), s)| **s == state && x >= x0 && y >= y0 && x <= x1 && y <= y1)
+            .map(|(&cell, _)| cell)
+            .collect()
+    }
+
+    pub fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        self.cells.keys().fold(None, |bounds, &(x, y)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y))),
+        })
+    }
+}
+
+// cells that changed state, as (cell, before, after), with the generations
+// on either side \\
+pub struct CircuitDiff {
+    cells: Vec<((i64, i64), State, State)>,
+    generations: (u64, u64),
+}
+
+impl Revision for Circuit {
+    type Diff = CircuitDiff;
+
+    fn diff(&self, before: &Circuit) -> Option<CircuitDiff> {
+        let mut cells: Vec<_> = self.cells.iter()
+            .filter(|&(cell, state)| before.get(cell.0, cell.1) != *state)
+            .map(|(&cell, &state)| (cell, before.get(cell.0, cell.1), state))
+            .collect();
+        cells.extend(before.cells.iter()
+            .filter(|(cell, _)| !self.cells.contains_key(cell))
+            .map(|(&cell, &state)| (cell, state, State::Empty)));
+        Some(CircuitDiff {cells, generations: (before.generation, self.generation)})
+    }
+
+    fn revert(&mut self, diff: &CircuitDiff) {
+        for &((x, y), state, _) in &diff.cells {
+            self.set(x, y, state);
+        }
+        self.generation = diff.generations.0;
+    }
+
+    fn apply(&mut self, diff: &CircuitDiff) {
+        for &((x, y), _, state) in &diff.cells {
+            self.set(x, y, state);
+        }
+        self.generation = diff.generations.1;
+    }
+
+    fn footprint(&self) -> usize {
+        mem::size_of::<Circuit>() + self.cells.capacity() * mem::size_of::<((i64, i64), State)>()
+    }
+
+    fn diff_footprint(diff: &CircuitDiff) -> usize {
+        mem::size_of::<CircuitDiff>() + diff.cells.capacity() * mem::size_of::<((i64, i64), State, State)>()
+    }
+}
+
+
+// cells a single run may paint, so a short file cannot ask for billions of them \\
+const MAX_PAINTED_RUN: i64 = 1 << 20;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// run-length encoded rows of states, as found in multistate RLE and in the `#L` lines of MCell \\
+fn read_runs(runs: &str) -> io::Result<Circuit> {
+    let mut rows: Vec<((i64, i64), State)> = Vec::new();
+    let (mut x, mut y): (i64, i64) = (0, 0);
+    let mut count = String::new();
+    for c in runs.chars() {
+        if c.is_ascii_digit() {
+            count.push(c);
+            continue;
+        }
+        let run: i64 = if count.is_empty() {1} else {count.parse().map_err(|_| invalid(count.clone()))?};
+        count.clear();
+        let too_long = || invalid(format!("run of {} cells does not fit in the pattern", run));
+        let state = match c {
+            'b' | '.' => Some(State::Empty),
+            'o' => Some(State::Head),
+            'A'..='X' => Some(State::from_number(c as u32 - 'A' as u32 + 1)
+                .ok_or_else(|| invalid(format!("state '{}' is not a Wireworld state", c)))?),
+            _ => None,
+        };
+        match (state, c) {
+            (Some(state), _) => {
+                let stop = x.checked_add(run).filter(|_| state == State::Empty || run <= MAX_PAINTED_RUN).ok_or_else(too_long)?;
+                if state != State::Empty {
+                    rows.extend((x..stop).map(|cx| ((cx, y), state)));
+                }
+                x = stop;
+            }
+            (None, '$') => {
+                y = y.checked_add(run).ok_or_else(too_long)?;
+                x = 0;
+            }
+            (None, '!') => break,
+            (None, c) if c.is_whitespace() => {}
+            (None, c) => return Err(invalid(format!("unexpected '{}' in Wireworld pattern", c))),
+        }
+    }
+    // rows are listed top down while y grows upward \\
+    let mut circuit = Circuit::new();
+    for ((x, y), state) in rows {
+        circuit.set(x, -y, state);
+    }
+    Ok(circuit)
+}
+
+pub fn load(path: &Path) -> io::Result<Circuit> {
+    let text = fs::read_to_string(path)?;
+    let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("mcl") => {
+            let game = text.lines().find_map(|line| line.strip_prefix("#GAME")).map(str::trim);
+            let rule = text.lines().find_map(|line| line.strip_prefix("#RULE")).map(str::trim);
+            if !game.is_some_and(|game| game.eq_ignore_ascii_case("wireworld")) && !rule.is_some_and(|rule| rule.eq_ignore_ascii_case("wireworld")) {
+                return Err(invalid(format!("not a Wireworld pattern: {}", path.display())));
+            }
+            let runs: String = text.lines().filter_map(|line| line.strip_prefix("#L")).collect();
+            read_runs(&runs)
+        }
+        Some("rle") => {
+            let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+            let header = lines.next().ok_or_else(|| invalid(format!("empty pattern: {}", path.display())))?;
+            let rule = header.split(',').filter_map(|field| field.split_once('=')).find(|(key, _)| key.trim() == "rule");
+            if !rule.is_some_and(|(_, rule)| rule.trim().eq_ignore_ascii_case("wireworld")) {
+                return Err(invalid(format!("not a Wireworld pattern: {}", path.display())));
+            }
+            read_runs(&lines.collect::<String>())
+        }
+        _ => Err(invalid(format!("unknown Wireworld format: {}", path.display()))),
+    }
+}
+
+
+struct Editor {
+    circuit: Circuit,
+    history: History<Circuit>,
+    brush: State,
+    tool: Tool,
+    mark: Option<(i64, i64)>,
+    mirror: Mirror,
+}
+
+impl Editor {
+    // paints the brush state with the current tool, toggling single cells back to empty \\
+    fn use_tool(&mut self, cursor: (i64, i64)) -> Option<String> {
+        let circuit = &mut self.circuit;
+        let brush = self.brush;
+        match self.tool {
+            Tool::Pencil => for (x, y) in self.mirror.images(cursor) {
+                circuit.set(x, y, if circuit.get(x, y) == brush {State::Empty} else {brush});
+            }
+            Tool::Fill => {
+                let frame = circuit.bounding_box().unwrap_or((0, 0, -1, -1));
+                match tools::flood_fill(cursor, frame, false, |x, y| circuit.get(x, y) != State::Empty) {
+                    Some(region) => for (x, y) in self.mirror.apply(&region) {
+                        circuit.set(x, y, brush);
+                    }
+                    None => return Some(String::from("only enclosed empty regions can be filled")),
+                }
+            }
+            tool => match self.mark.take() {
+                None => self.mark = Some(cursor),
+                Some(mark) => for (x, y) in self.mirror.apply(&tool.shape(mark, cursor)) {
+                    circuit.set(x, y, brush);
+                }
+            }
+        }
+        None
+    }
+
+    fn tool_preview(&self, cursor: (i64, i64), visible: (i64, i64, i64, i64)) -> Vec<(i64, i64)> {
+        match self.mark {
+            Some(mark) => tools::preview(self.tool, self.mirror, mark, cursor, visible),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Mode for Editor {
+    fn name(&self) -> String {
+        String::from("Wireworld")
+    }
+
+    fn help(&self) -> &'static str {
+        HELP
+    }
+
+    fn stats(&self) -> Vec<Span<'static>> {
+        vec![
+            Span::raw(format!(
+                "Generation: {} | Wire: {} | Electrons: {} | Tool: {} | Brush: ",
+                self.circuit.generation,
+                self.circuit.cells.len(),
+                self.circuit.count(State::Head),
+                self.tool.name(),
+            )),
+            Span::styled(format!("█ {}", self.brush.name()), Style::default().fg(self.brush.color())),
+        ]
+    }
+
+    fn paint(&self, ctx: &mut Context, controls: &Controls) {
+        let view = &controls.view;
+        let (w, h) = view.span();
+        let visible = (view.x, view.y, view.x + w - 1, view.y + h - 1);
+        for state in [State::Conductor, State::Tail, State::Head] {
+            paint_population(ctx, view, &self.circuit.cells_in(state, visible), state.color());
+        }
+        if let Some(cursor) = &controls.cursor {
+            paint_population(ctx, view, &self.tool_preview((cursor.x, cursor.y), visible), Color::Gray);
+        }
+    }
+
+    fn step(&mut self) {
+        self.circuit.step();
+    }
+
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        self.circuit.bounding_box()
+    }
+
+    fn advance(&mut self, controls: &Controls) {
+        if !controls.paused {
+            let before = self.circuit.clone();
+            controls.turbo(|| self.circuit.step());
+            self.history.record(Change::Step, before, &self.circuit);
+        }
+    }
+
+    fn key(&mut self, key: KeyEvent, controls: &mut Controls) -> io::Result<bool> {
+        let cursor = controls.cursor.as_ref().map_or((0, 0), |cursor| (cursor.x, cursor.y));
+        let change = match key.code {
+            KeyCode::Char('n') if controls.paused => Some(Change::Step),
+            KeyCode::Char('c') | KeyCode::Char('s') | KeyCode::Enter => Some(Change::Edit),
+            _ => None,
+        };
+        let before = change.map(|_| self.circuit.clone());
+        match key.code {
+            KeyCode::Char('u') => self.history.undo(&mut self.circuit),
+            KeyCode::Char('U') => self.history.redo(&mut self.circuit),
+            KeyCode::Char('b') if controls.paused => self.history.step_back(&mut self.circuit),
+            KeyCode::Char('n') if controls.paused => self.circuit.step(),
+            KeyCode::Char('c') => {
+                self.circuit = Circuit::new();
+                controls.paused = true;
+            }
+            KeyCode::Char('s') | KeyCode::Enter => controls.status = self.use_tool(cursor),
+            KeyCode::Tab => self.brush = self.brush.next(),
+            KeyCode::Char(c @ '1'..='4') => self.brush = State::ALL[c as usize - '1' as usize],
+            KeyCode::Char('w') => {
+                self.tool = self.tool.next();
+                self.mark = None;
+                controls.status = Some(format!("tool: {}", self.tool.name()));
+            }
+            KeyCode::Char('M') => {
+                self.mirror = self.mirror.next(cursor.0, cursor.1);
+                controls.status = Some(self.mirror.describe());
+            }
+            KeyCode::Esc => self.mark = None,
+            _ => return Ok(false),
+        }
+        if let (Some(change), Some(before)) = (change, before) {
+            if before.generation != self.circuit.generation || before.cells != self.circuit.cells {
+                self.history.record(change, before, &self.circuit);
+            }
+        }
+        Ok(true)
+    }
+}
+
+
+pub fn run_wireworld<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+    run_circuit(terminal, Circuit::new())
+}
+
+// entry point for `game_collection wireworld ...` \\
+pub fn run_wireworld_cli(args: &[String]) -> io::Result<()> {
+    let circuit = match args {
+        [] => Circuit::new(),
+        [pattern] if !pattern.starts_with('-') => load(Path::new(pattern))?,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, USAGE)),
+    };
+    crate::with_terminal(|terminal| run_circuit(terminal, circuit))
+}
+
+fn run_circuit<B: Backend>(terminal: &mut Terminal<B>, circuit: Circuit) -> io::Result<()> {
+    let mut controls = Controls::new(terminal.size()?, Some(Cursor {x: 0, y: 0}));
+    if let Some(bounding_box) = circuit.bounding_box() {
+        controls.view.fit(bounding_box);
+        controls.cursor = Some(Cursor {x: (bounding_box.0 + bounding_box.2) / 2, y: (bounding_box.1 + bounding_box.3) / 2});
+    }
+    let mut editor = Editor {
+        circuit,
+        history: History::new(),
+        brush: State::Conductor,
+        tool: Tool::Pencil,
+        mark: None,
+        mirror: Mirror::Off,
+    };
+    run_mode(terminal, &mut editor, controls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a straight wire with an electron heading right: tail at x = 0, head at x = 1 \\
+    fn wire() -> Circuit {
+        read_runs("BA4C!").unwrap()
+    }
+
+    #[test]
+    fn electrons_run_along_a_wire() {
+        let mut circuit = wire();
+        circuit.step();
+        assert!(circuit.get(0, 0) == State::Conductor);
+        assert!(circuit.get(1, 0) == State::Tail);
+        assert!(circuit.get(2, 0) == State::Head);
+        assert_eq!(circuit.count(State::Head), 1);
+        for _ in 0..3 {
+            circuit.step();
+        }
+        assert!(circuit.get(5, 0) == State::Head);
+        circuit.step();
+        assert_eq!(circuit.count(State::Head), 0);
+    }
+
+    #[test]
+    fn conductors_with_three_heads_around_stay_put() {
+        let mut circuit = read_runs("3A$bC!").unwrap();
+        circuit.step();
+        assert!(circuit.get(1, -1) == State::Conductor);
+        let mut circuit = read_runs("2A$bC!").unwrap();
+        circuit.step();
+        assert!(circuit.get(1, -1) == State::Head);
+    }
+
+    #[test]
+    fn reads_runs_top_down() {
+        let circuit = read_runs("C$2bA$B!").unwrap();
+        assert!(circuit.get(0, 0) == State::Conductor);
+        assert!(circuit.get(2, -1) == State::Head);
+        assert!(circuit.get(0, -2) == State::Tail);
+        assert!(read_runs("E!").is_err());
+        assert!(read_runs("C?").is_err());
+    }
+
+    #[test]
+    fn rejects_runs_that_do_not_fit() {
+        assert!(read_runs("2000000C!").is_err());
+        assert!(read_runs("9223372036854775807b2C!").is_err());
+        assert!(read_runs("9223372036854775807$$C!").is_err());
+        let circuit = read_runs("9223372036854775806bC!").unwrap();
+        assert!(circuit.get(i64::MAX - 1, 0) == State::Conductor);
+    }
+
+    #[test]
+    fn history_undoes_circuit_changes() {
+        let mut history = History::new();
+        let mut circuit = wire();
+        let before = circuit.clone();
+        circuit.step();
+        history.record(Change::Step, before, &circuit);
+        let before = circuit.clone();
+        circuit.set(9, 9, State::Conductor);
+        history.record(Change::Edit, before, &circuit);
+        history.undo(&mut circuit);
+        assert!(circuit.get(9, 9) == State::Empty);
+        history.step_back(&mut circuit);
+        assert_eq!(circuit.generation, 0);
+        assert!(circuit.get(1, 0) == State::Head);
+        history.redo(&mut circuit);
+        assert_eq!(circuit.generation, 1);
+    }
+}
@@ -1,9 +1,10 @@
 mod game_of_life;
-use crate::game_of_life::run_gol;
+use crate::game_of_life::{run_gol, run_cli, run_wireworld, run_wireworld_cli, run_turmites, run_turmites_cli, run_elementary, run_elementary_cli, run_immigration, run_immigration_cli};
 mod snake;
 use crate::snake::run_snake;
 mod cube;
 use crate::cube::run_cube;
+mod render;
 
 use std::io;
 use tui::{
@@ -20,14 +21,18 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
-const GAMES: [&str; 4] = [
+const GAMES: [&str; 8] = [
         "Game of Life",
         "Snake",
         "Cube",
+        "Wireworld",
+        "Langton's Ant",
+        "Elementary CA",
+        "Immigration",
         "Sudoku"
 ];
 
-const DESCRIPTION: [&str; 4] = [
+const DESCRIPTION: [&str; 8] = [
     "Conway's Game of Life:
 -Underpopulation: Any live cell with fewer than two live neighbours dies.
 -Stable population: Any live cell with two or three live neighbours lives on to the next generation.
@@ -37,6 +42,23 @@ const DESCRIPTION: [&str; 4] = [
     Control a snake, eat apples but not yourself and don't crash into walls !",
     "Cube:
     Rotate a 3D rendered cube.",
+    "Wireworld:
+-Empty cells stay empty.
+-Electron heads become electron tails.
+-Electron tails become conductors.
+-Conductors become electron heads when one or two of their neighbours are electron heads.",
+    "Langton's Ant:
+-An ant on a white cell turns right, on a black cell turns left.
+-It flips the color of the cell it leaves and moves forward.
+-Turn strings like RLR or LLRR give ants more colors, turmite tables give them states.",
+    "Elementary cellular automata:
+-A single row of cells, each generation drawn below the previous one.
+-A cell's next state depends on itself and its two neighbours.
+-The rule number (0-255) lists the outcome of the eight neighbourhoods, as in rules 30, 90 and 110.",
+    "Immigration and QuadLife:
+-Conway's rules, with live cells of two (Immigration) or four (QuadLife) colors.
+-A newborn cell takes the color most of its three parents share, or the missing one.
+-Two players take turns placing cells, then the board runs: the largest population wins.",
     ""
 ];
 
@@ -46,6 +68,10 @@ fn start_game<B: Backend>(terminal: &mut Terminal<B>, idx: &mut usize) -> io::Re
         0 => run_gol(terminal),
         1 => run_snake(terminal),
         2 => run_cube(terminal),
+        3 => run_wireworld(terminal),
+        4 => run_turmites(terminal),
+        5 => run_elementary(terminal),
+        6 => run_immigration(terminal),
         _ => Ok(()),
     }
 }
@@ -86,7 +112,7 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
                 KeyCode::Char('q') => return Ok(()),
                 KeyCode::Esc => return Ok(()),
                 KeyCode::Enter => start_game(terminal, &mut list_idx),
-                KeyCode::Up => {if list_idx > 0 {list_idx -= 1}; Ok(())},
+                KeyCode::Up => {list_idx = list_idx.saturating_sub(1); Ok(())},
                 KeyCode::Down => {if list_idx < GAMES.len() - 1 {list_idx += 1}; Ok(())},
                 _ => {Ok(())}
             }.ok(); // TODO error handling
@@ -94,16 +120,27 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
     }
 }
 
-fn main() -> Result<(), io::Error> {
-    // setup terminal \\
+fn run_command(args: &[String]) -> io::Result<()> {
+    match args[0].as_str() {
+        "life" => run_cli(&args[1..]),
+        "wireworld" => run_wireworld_cli(&args[1..]),
+        "ant" => run_turmites_cli(&args[1..]),
+        "elementary" => run_elementary_cli(&args[1..]),
+        "immigration" => run_immigration_cli(&args[1..]),
+        command => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown command: {}", command))),
+    }
+}
+
+// sets up the terminal around a terminal user interface \\
+pub fn with_terminal<F>(f: F) -> io::Result<()>
+where F: FnOnce(&mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // start ui \\
-    let res = run(&mut terminal);
+    let res = f(&mut terminal);
 
     // restore terminal \\
     disable_raw_mode()?;
@@ -113,8 +150,22 @@ fn main() -> Result<(), io::Error> {
         DisableMouseCapture
     )?;
     terminal.show_cursor()?;
+    res
+}
 
-    if let Err(err) = res {println!("{:?}", err)}
+fn main() -> Result<(), io::Error> {
+    // headless commands \\
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(err) = run_command(&args) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // start ui \\
+    if let Err(err) = with_terminal(run) {println!("{:?}", err)}
 
     Ok(())
 }
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+// Grid games can pack several cells into each terminal character: half
+// blocks stack two cells, which also makes them as wide as they are tall,
+// and Braille patterns hold a 2x4 grid of dots. Cell coordinates grow
+// upward like the canvas rows, dot rows inside a character are counted
+// from the top like the glyphs.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Renderer {
+    Block,
+    HalfBlock,
+    Braille,
+}
+
+impl Renderer {
+    const ALL: [Renderer; 3] = [Renderer::Block, Renderer::HalfBlock, Renderer::Braille];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Renderer::Block => "block",
+            Renderer::HalfBlock => "half-block",
+            Renderer::Braille => "braille",
+        }
+    }
+
+    pub fn next(self) -> Renderer {
+        let idx = Renderer::ALL.iter().position(|r| *r == self).unwrap();
+        Renderer::ALL[(idx + 1) % Renderer::ALL.len()]
+    }
+
+    // (columns, rows) of cells held by one character \\
+    pub fn dots(self) -> (i64, i64) {
+        match self {
+            Renderer::Block => (1, 1),
+            Renderer::HalfBlock => (1, 2),
+            Renderer::Braille => (2, 4),
+        }
+    }
+
+    fn bit(self, col: i64, row: i64) -> u8 {
+        match (self, col, row) {
+            (Renderer::Braille, 0, 3) => 0x40,
+            (Renderer::Braille, 1, 3) => 0x80,
+            (Renderer::Braille, col, row) => 1 << (col * 3 + row),
+            (_, _, row) => 1 << row,
+        }
+    }
+
+    pub fn glyph(self, mask: u8) -> String {
+        match self {
+            Renderer::Braille => char::from_u32(0x2800 + mask as u32).unwrap().to_string(),
+            Renderer::HalfBlock if mask == 1 => String::from("▀"),
+            Renderer::HalfBlock if mask == 2 => String::from("▄"),
+            _ => String::from("█"),
+        }
+    }
+
+    // dot masks of the characters covering some cells \\
+    pub fn rasterize(self, cells: impl IntoIterator<Item = (i64, i64)>) -> HashMap<(i64, i64), u8> {
+        let (cols, rows) = self.dots();
+        let mut chars = HashMap::new();
+        for (x, y) in cells {
+            let position = (x.div_euclid(cols), y.div_euclid(rows));
+            let bit = self.bit(x.rem_euclid(cols), rows - 1 - y.rem_euclid(rows));
+            *chars.entry(position).or_insert(0) |= bit;
+        }
+        chars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn braille_dots_follow_the_unicode_layout() {
+        // dots 1-2-3-7 down the left column, 4-5-6-8 down the right \\
+        let dots = [((0, 3), 0x01), ((0, 2), 0x02), ((0, 1), 0x04), ((0, 0), 0x40), ((1, 3), 0x08), ((1, 2), 0x10), ((1, 1), 0x20), ((1, 0), 0x80)];
+        for ((x, y), bit) in dots {
+            assert_eq!(Renderer::Braille.rasterize([(x, y)]), HashMap::from([((0, 0), bit)]), "({}, {})", x, y);
+        }
+        let block = Renderer::Braille.rasterize(dots.map(|(cell, _)| cell));
+        assert_eq!(block, HashMap::from([((0, 0), 0xff)]));
+        assert_eq!(Renderer::Braille.glyph(0xff), "⣿");
+        assert_eq!(Renderer::Braille.glyph(0x01 | 0x80), "⢁");
+    }
+
+    #[test]
+    fn cells_left_of_and_below_the_origin_land_in_their_own_characters() {
+        let chars = Renderer::Braille.rasterize([(-1, -1), (-2, -4), (2, 4)]);
+        assert_eq!(chars, HashMap::from([((-1, -1), 0x08 | 0x40), ((1, 1), 0x40)]));
+    }
+
+    #[test]
+    fn half_blocks_stack_two_cells() {
+        let chars = Renderer::HalfBlock.rasterize([(0, 1), (1, 0), (2, 0), (2, 1)]);
+        assert_eq!(chars, HashMap::from([((0, 0), 1), ((1, 0), 2), ((2, 0), 3)]));
+        assert_eq!(Renderer::HalfBlock.glyph(1), "▀");
+        assert_eq!(Renderer::HalfBlock.glyph(2), "▄");
+        assert_eq!(Renderer::HalfBlock.glyph(3), "█");
+        assert_eq!(Renderer::Block.rasterize([(5, -3)]), HashMap::from([((5, -3), 1)]));
+    }
+}
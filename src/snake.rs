@@ -1,7 +1,7 @@
 use std::{io, format, time::{Duration, Instant}};
 use tui::{
     backend::Backend,
-    widgets::{Block, Borders, Paragraph, canvas::{Canvas, Points}},
+    widgets::{Block, Borders, Paragraph, canvas::Canvas},
     layout::{Layout, Constraint},
     style::{Color, Style},
     text::Span,
@@ -13,8 +13,9 @@ use crossterm::{
     terminal::size,
 };
 use rand::Rng;
+use crate::render::Renderer;
 
-const HELP: &str = "[r]: 'reset game', [arrows]: 'change direction'";
+const HELP: &str = "[r]: 'reset game', [arrows]: 'change direction', [m]: 'renderer'";
 
 #[derive(PartialEq)]
 enum Direction {
@@ -92,7 +93,9 @@ fn snake_eats_apple(snake: &Snake, apple_coords: (f64, f64)) -> bool {
 
 pub fn run_snake<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
     let (c, r) = size().unwrap();
-    let (cols, rows) = ((c-2) as u32, (r-5) as u32);
+    let (chars_x, chars_y) = ((c-2) as u32, (r-5) as u32);
+    let mut renderer = Renderer::Block;
+    let (mut cols, mut rows) = (chars_x, chars_y);
 
     // snake creation \\
     let mut snake: Snake = Snake::new((cols/2) as f64, (rows/2) as f64);
@@ -113,20 +116,26 @@ pub fn run_snake<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
             f.render_widget(paragraph, chunks[0]);
             
             // canvas \\
-            let title = format!("[Snake: size={}]", snake.body.len());
+            let title = match renderer {
+                Renderer::Block => format!("[Snake: size={}]", snake.body.len()),
+                _ => format!("[Snake: size={} | {}]", snake.body.len(), renderer.name()),
+            };
             let canvas = Canvas::default()
                 .block(Block::default().title(title).borders(Borders::ALL))
-                .x_bounds([0.0, (cols-1) as f64])
-                .y_bounds([0.0, (rows-1) as f64])
+                .x_bounds([0.0, (chars_x-1) as f64])
+                .y_bounds([0.0, (chars_y-1) as f64])
                 .marker(symbols::Marker::Block)
                 .paint(|ctx| {
-                    for (x, y) in snake.body.clone() {
-                        ctx.print(x, y, Span::styled("█", Style::default().fg(snake.color)))
+                    // a character holding part of the apple is drawn red \\
+                    let body = renderer.rasterize(snake.body.iter().map(|&(x, y)| (x as i64, y as i64)));
+                    let apple = renderer.rasterize([(apple_coords.0 as i64, apple_coords.1 as i64)]);
+                    for (&(sx, sy), &mask) in body.iter().filter(|(position, _)| !apple.contains_key(position)) {
+                        ctx.print(sx as f64, sy as f64, Span::styled(renderer.glyph(mask), Style::default().fg(snake.color)))
+                    }
+                    for ((sx, sy), mask) in apple {
+                        let mask = mask | body.get(&(sx, sy)).copied().unwrap_or(0);
+                        ctx.print(sx as f64, sy as f64, Span::styled(renderer.glyph(mask), Style::default().fg(Color::Red)))
                     }
-                    ctx.draw(&Points {
-                        coords: &[apple_coords],
-                        color: Color::Red
-                    });
                 });
             f.render_widget(canvas, chunks[1]);
         })?;
@@ -145,6 +154,13 @@ pub fn run_snake<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
                         snake = Snake::new((cols/2) as f64, (rows/2) as f64);
                         apple_coords = summon_apple(&snake, cols, rows);
                     },
+                    KeyCode::Char('m') => {
+                        renderer = renderer.next();
+                        let (dots_x, dots_y) = renderer.dots();
+                        (cols, rows) = (chars_x * dots_x as u32, chars_y * dots_y as u32);
+                        snake = Snake::new((cols/2) as f64, (rows/2) as f64);
+                        apple_coords = summon_apple(&snake, cols, rows);
+                    },
                     KeyCode::Left => snake.go(Direction::Left),
                     KeyCode::Right => snake.go(Direction::Right),
                     KeyCode::Up => snake.go(Direction::Up),